@@ -0,0 +1,33 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+
+/// Pass the dendritic version pinned in Cargo.lock to the crate as
+/// `DENDRITIC_VERSION`, so benchmarks record it wherever they run from.
+fn main() {
+
+    let manifest_dir = env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let Some(lock) = Path::new(&manifest_dir)
+        .ancestors()
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|path| path.is_file()) else {
+        return;
+    };
+    println!("cargo:rerun-if-changed={}", lock.display());
+
+    if let Some(version) = fs::read_to_string(&lock).ok().as_deref().and_then(dendritic_version) {
+        println!("cargo:rustc-env=DENDRITIC_VERSION={version}");
+    }
+}
+
+
+/// Version of the `dendritic` package in a lock file
+fn dendritic_version(lock: &str) -> Option<String> {
+    let mut lines = lock.lines().skip_while(|line| *line != "name = \"dendritic\"");
+    lines.next()?;
+    lines.next()?
+        .strip_prefix("version = \"")?
+        .strip_suffix('"')
+        .map(|version| version.to_string())
+}
@@ -0,0 +1,82 @@
+use std::fs;
+
+use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+
+use crate::config::*;
+use crate::error::*;
+
+
+/// File run metadata is saved to inside a model artifact directory
+pub const METADATA_FILE: &str = "metadata.json";
+
+
+/// Settings a model was trained with, saved so the run can be reproduced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunMetadata {
+
+    /// Name of experiment that trained the model
+    pub experiment: String,
+
+    /// Seed driving split shuffling and mini-batch ordering
+    pub seed: u64,
+
+    /// Strategy used to split rows
+    pub split: SplitKind,
+
+    /// Number of training rows
+    pub train_rows: usize,
+
+    /// Number of validation rows
+    pub validation_rows: usize,
+
+    /// Number of test rows
+    pub test_rows: usize,
+
+    /// Model construction settings
+    pub model: ModelConfig,
+
+    /// Training loop settings
+    pub hyperparameters: HyperparameterConfig
+}
+
+
+impl RunMetadata {
+
+    /// Save run metadata to model artifact directory
+    pub fn save(&self, dir: &str) -> PipelineResult<()> {
+        write_json(dir, METADATA_FILE, self)
+    }
+
+    /// Load run metadata from model artifact directory
+    pub fn load(dir: &str) -> PipelineResult<Self> {
+        read_json(dir, METADATA_FILE)
+    }
+
+}
+
+
+/// Write value as pretty printed JSON to a file in an artifact directory.
+///
+/// # Arguments
+///
+/// * `dir` - Artifact directory, created if missing.
+/// * `file` - Name of file within the directory.
+/// * `value` - Value to serialize.
+///
+pub fn write_json<T: Serialize>(dir: &str, file: &str, value: &T) -> PipelineResult<()> {
+    let path = format!("{dir}/{file}");
+    fs::create_dir_all(dir).map_err(|err| PipelineError::artifact(dir, err))?;
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|err| PipelineError::artifact(&path, err))?;
+    fs::write(&path, json).map_err(|err| PipelineError::artifact(&path, err))
+}
+
+
+/// Read JSON file from an artifact directory
+pub fn read_json<T: DeserializeOwned>(dir: &str, file: &str) -> PipelineResult<T> {
+    let path = format!("{dir}/{file}");
+    let contents = fs::read_to_string(&path)
+        .map_err(|err| PipelineError::artifact(&path, err))?;
+    serde_json::from_str(&contents).map_err(|err| PipelineError::artifact(&path, err))
+}
@@ -0,0 +1,307 @@
+use ndarray::{s, Array1, Array2, ArrayView1};
+use rand::{Rng, RngExt};
+use serde::{Serialize, Deserialize};
+
+use crate::Task;
+use crate::config::{ExperimentConfig, SplitKind};
+use crate::evaluation::Metrics;
+use crate::metrics::{ClassificationMetrics, RegressionMetrics};
+
+
+/// Naive models a trained model is benchmarked against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Baseline {
+
+    /// Mean of the training targets
+    Mean,
+
+    /// Median of the training targets
+    Median,
+
+    /// Most frequent training class, with the class priors as probabilities
+    Majority,
+
+    /// Class drawn at random from the training class priors
+    Stratified,
+
+    /// Target of the previous row
+    Persistence,
+
+    /// Target of the row one season earlier
+    SeasonalNaive
+}
+
+
+impl Baseline {
+
+    /// Name of baseline as shown in reports
+    pub fn name(&self) -> &'static str {
+        match self {
+            Baseline::Mean => "mean",
+            Baseline::Median => "median",
+            Baseline::Majority => "majority",
+            Baseline::Stratified => "stratified",
+            Baseline::Persistence => "persistence",
+            Baseline::SeasonalNaive => "seasonal_naive"
+        }
+    }
+
+    /// Baselines that apply to the task and split of an experiment.
+    ///
+    /// Forecasters need rows in time order, so they are only used with a
+    /// chronological split, and seasonal naive only when a season is set.
+    pub fn for_config(config: &ExperimentConfig) -> Vec<Baseline> {
+        match config.task() {
+            Task::Regression => {
+                let mut baselines = vec![Baseline::Mean, Baseline::Median];
+                if config.dataset.split == SplitKind::Chronological {
+                    baselines.push(Baseline::Persistence);
+                    if config.dataset.season.is_some() {
+                        baselines.push(Baseline::SeasonalNaive);
+                    }
+                }
+                baselines
+            },
+            Task::Binary | Task::Multiclass => vec![Baseline::Majority, Baseline::Stratified]
+        }
+    }
+
+}
+
+
+/// Metrics of a baseline on held out rows
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BaselineScore {
+
+    /// Baseline scored
+    pub baseline: Baseline,
+
+    /// Number of rows scored, forecasters skip rows without enough history
+    pub rows: usize,
+
+    /// Metrics on the scored rows
+    pub metrics: Metrics
+}
+
+
+/// Fit every baseline that applies to an experiment and score it.
+///
+/// # Arguments
+///
+/// * `config` - Experiment the baselines are chosen for.
+/// * `y` - Raw target of every row in file order (class labels for classification).
+/// * `train` - Rows baselines are fit on.
+/// * `test` - Rows baselines are scored on.
+/// * `rng` - Random source for the stratified classifier.
+///
+pub fn score_baselines<R: Rng + ?Sized>(
+    config: &ExperimentConfig,
+    y: &ArrayView1<f64>,
+    train: &[usize],
+    test: &[usize],
+    rng: &mut R) -> Vec<BaselineScore> {
+
+    let y_train: Vec<f64> = train.iter().map(|&row| y[row]).collect();
+    let y_test: Array1<f64> = test.iter().map(|&row| y[row]).collect();
+    if y_train.is_empty() || y_test.is_empty() {
+        return Vec::new();
+    }
+
+    let task = config.task();
+    let classes = class_count(y_train.iter().chain(y_test.iter()));
+    let mut scores = Vec::new();
+
+    for baseline in Baseline::for_config(config) {
+        let score = match baseline {
+            Baseline::Mean => {
+                let mean = y_train.iter().sum::<f64>() / y_train.len() as f64;
+                constant(baseline, mean, &y_test)
+            },
+            Baseline::Median => constant(baseline, median(&y_train), &y_test),
+            Baseline::Persistence => forecast(baseline, 1, y, test),
+            Baseline::SeasonalNaive => match config.dataset.season {
+                Some(season) => forecast(baseline, season, y, test),
+                None => None
+            },
+            Baseline::Majority => {
+                let priors = class_priors(&y_train, classes);
+                let probabilities = Array2::from_shape_fn((test.len(), classes), |(_, class)| priors[class]);
+                Some(classify(baseline, task, &y_test, probabilities))
+            },
+            Baseline::Stratified => {
+                let priors = class_priors(&y_train, classes);
+                let mut probabilities = Array2::zeros((test.len(), classes));
+                for mut row in probabilities.rows_mut() {
+                    row[draw(&priors, rng)] = 1.0;
+                }
+                Some(classify(baseline, task, &y_test, probabilities))
+            }
+        };
+        scores.extend(score);
+    }
+
+    scores
+}
+
+
+/// Score a constant prediction
+fn constant(baseline: Baseline, value: f64, y_test: &Array1<f64>) -> Option<BaselineScore> {
+    let predictions = Array1::from_elem(y_test.len(), value);
+    Some(BaselineScore {
+        baseline,
+        rows: y_test.len(),
+        metrics: Metrics::Regression(RegressionMetrics::compute(&y_test.view(), &predictions.view()))
+    })
+}
+
+
+/// Score predicting each row with the target `lag` rows earlier
+fn forecast(baseline: Baseline, lag: usize, y: &ArrayView1<f64>, test: &[usize]) -> Option<BaselineScore> {
+
+    let rows: Vec<usize> = test.iter().copied().filter(|&row| row >= lag).collect();
+    if rows.is_empty() {
+        return None;
+    }
+
+    let y_true: Array1<f64> = rows.iter().map(|&row| y[row]).collect();
+    let predictions: Array1<f64> = rows.iter().map(|&row| y[row - lag]).collect();
+    Some(BaselineScore {
+        baseline,
+        rows: rows.len(),
+        metrics: Metrics::Regression(RegressionMetrics::compute(&y_true.view(), &predictions.view()))
+    })
+}
+
+
+/// Score class probabilities, keeping only the positive column for binary tasks
+fn classify(baseline: Baseline, task: Task, y_test: &Array1<f64>, probabilities: Array2<f64>) -> BaselineScore {
+
+    let labels: Vec<usize> = y_test.iter().map(|&label| label as usize).collect();
+    let probabilities = match task {
+        Task::Binary => probabilities.slice_move(s![.., 1..2]),
+        Task::Regression | Task::Multiclass => probabilities
+    };
+
+    BaselineScore {
+        baseline,
+        rows: labels.len(),
+        metrics: Metrics::Classification(ClassificationMetrics::compute(&labels, &probabilities.view()))
+    }
+}
+
+
+/// Number of classes given the labels seen, at least two
+fn class_count<'a>(labels: impl Iterator<Item = &'a f64>) -> usize {
+    labels.map(|&label| label as usize + 1).max().unwrap_or(0).max(2)
+}
+
+
+/// Fraction of training rows in each class
+fn class_priors(y_train: &[f64], classes: usize) -> Vec<f64> {
+    let mut priors = vec![0.0; classes];
+    for &label in y_train {
+        priors[label as usize] += 1.0;
+    }
+    priors.iter().map(|count| count / y_train.len() as f64).collect()
+}
+
+
+/// Draw a class with probability given by its prior
+fn draw<R: Rng + ?Sized>(priors: &[f64], rng: &mut R) -> usize {
+    let mut remaining: f64 = rng.random();
+    for (class, &prior) in priors.iter().enumerate() {
+        if remaining < prior {
+            return class;
+        }
+        remaining -= prior;
+    }
+    priors.len() - 1
+}
+
+
+fn median(values: &[f64]) -> f64 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let mid = sorted.len() / 2;
+    match sorted.len() % 2 {
+        0 => (sorted[mid - 1] + sorted[mid]) / 2.0,
+        _ => sorted[mid]
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::*;
+    use crate::dataset::{read_table, select_array};
+    use crate::monero;
+    use crate::split::{split_indices, SplitSizes};
+
+    fn regression(score: &BaselineScore) -> &RegressionMetrics {
+        match &score.metrics {
+            Metrics::Regression(metrics) => metrics,
+            Metrics::Classification(_) => panic!("{} scored as a classifier", score.baseline.name())
+        }
+    }
+
+    #[test]
+    fn forecasters_lag_the_target() {
+        let mut config = monero::config();
+        config.dataset.season = Some(3);
+        let y = Array1::from_iter((0..12).map(|row| (row % 3) as f64 * 10.0 + row as f64));
+        let scores = score_baselines(&config, &y.view(), &(0..8).collect::<Vec<_>>(), &[8, 9, 10, 11], &mut StdRng::seed_from_u64(0));
+
+        let names: Vec<&str> = scores.iter().map(|score| score.baseline.name()).collect();
+        assert_eq!(names, ["mean", "median", "persistence", "seasonal_naive"]);
+
+        let seasonal = regression(&scores[3]);
+        assert_eq!(scores[3].rows, 4);
+        assert!((seasonal.mae - 3.0).abs() < 1e-12);
+        let persistence = regression(&scores[2]);
+        assert!((persistence.mae - (11.0 + 11.0 + 19.0 + 11.0) / 4.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn forecasters_skip_rows_without_history() {
+        let mut config = monero::config();
+        config.dataset.season = Some(5);
+        let y = Array1::from_iter((0..6).map(f64::from));
+        let scores = score_baselines(&config, &y.view(), &[4, 5], &[0, 1, 2], &mut StdRng::seed_from_u64(0));
+
+        let persistence = scores.iter().find(|score| score.baseline == Baseline::Persistence).unwrap();
+        assert_eq!(persistence.rows, 2);
+        assert!(scores.iter().all(|score| score.baseline != Baseline::SeasonalNaive));
+    }
+
+    #[test]
+    fn forecasters_score_monero_closes() {
+        let config = monero::config();
+        let dataset = &config.dataset;
+        let df = read_table(&dataset.path).unwrap();
+        let y = select_array(&df, &dataset.path, std::slice::from_ref(&dataset.target)).unwrap();
+        let y = y.column(0);
+
+        let sizes = SplitSizes { validation: dataset.validation_size, test: dataset.test_size };
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let indices = split_indices(dataset.split, y.len(), sizes, None, None, &mut rng).unwrap();
+        let scores = score_baselines(&config, &y, &indices.train, &indices.test, &mut rng);
+
+        let names: Vec<&str> = scores.iter().map(|score| score.baseline.name()).collect();
+        assert_eq!(names, ["mean", "median", "persistence", "seasonal_naive"]);
+
+        let test = &indices.test;
+        let expected = test.iter().map(|&row| (y[row] - y[row - 1]).abs()).sum::<f64>() / test.len() as f64;
+        assert_eq!(scores[2].rows, test.len());
+        assert!((regression(&scores[2]).mae - expected).abs() < 1e-9);
+
+        // closes follow the previous day far more closely than the training mean
+        assert!(regression(&scores[2]).mae < regression(&scores[0]).mae);
+        assert!(regression(&scores[2]).mae < regression(&scores[3]).mae);
+    }
+
+}
@@ -0,0 +1,223 @@
+use std::path::Path;
+
+use ndarray::Axis;
+use polars::prelude::*;
+
+use crate::Task;
+use crate::dataset::{read_table, select_array, write_table};
+use crate::error::*;
+use crate::export::ExportedModel;
+
+
+/// Column holding the position of every row in the input file
+pub const ROW_ID_COLUMN: &str = "row_id";
+
+/// Column holding the predicted target, or class code for classifiers
+pub const PREDICTION_COLUMN: &str = "prediction";
+
+/// Column holding the name of the predicted class
+pub const LABEL_COLUMN: &str = "label";
+
+/// Prefix of the column holding the probability of each class
+pub const PROBABILITY_PREFIX: &str = "probability_";
+
+
+/// Outcome of scoring a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchPredictions {
+
+    /// Number of rows scored
+    pub rows: usize,
+
+    /// File the scored rows were written to
+    pub output: String
+}
+
+
+/// Score every row of a parquet or CSV file with an exported model.
+///
+/// The model's feature columns are selected by name, so the input may
+/// hold other columns in any order, and the stored preprocessing is
+/// applied to them. The output holds every input column, a `row_id`
+/// column, the prediction and, for classifiers, the predicted label and
+/// the probability of every class.
+///
+/// # Arguments
+///
+/// * `exported` - Model to score the rows with, see `tabular::load_export`.
+/// * `input` - Parquet or CSV file of rows to score.
+/// * `output` - File to write, `<input>_predictions` in the input's format when `None`.
+///
+pub fn predict_file(exported: &ExportedModel, input: &str, output: Option<&str>) -> PipelineResult<BatchPredictions> {
+
+    let output = match output {
+        Some(output) => output.to_string(),
+        None => default_output(input)
+    };
+    if Path::new(&output) == Path::new(input) {
+        return Err(PipelineError::Config(format!("predictions would overwrite the input file {input}")));
+    }
+
+    let mut df = read_table(input)?;
+
+    for feature in exported.features() {
+        if let Ok(column) = df.column(feature) && column.null_count() > 0 {
+            return Err(PipelineError::InvalidDataset {
+                path: input.to_string(),
+                message: format!("feature column {feature} has {} missing values", column.null_count())
+            });
+        }
+    }
+    let x = select_array(&df, input, exported.features())?;
+
+    let mut columns = vec![Column::new(ROW_ID_COLUMN.into(), (0..df.height() as u64).collect::<Vec<_>>())];
+    match exported.task {
+        Task::Regression => {
+            let predictions = exported.predict(&x)?;
+            columns.push(Column::new(PREDICTION_COLUMN.into(), predictions.column(0).to_vec()));
+        },
+        Task::Binary | Task::Multiclass => {
+            let names = exported.classes();
+            let (classes, probabilities) = exported.classify(&x)?;
+            columns.push(Column::new(PREDICTION_COLUMN.into(), classes.iter().map(|&class| class as u32).collect::<Vec<_>>()));
+            columns.push(Column::new(LABEL_COLUMN.into(), classes.iter().map(|&class| names[class].clone()).collect::<Vec<_>>()));
+            for (name, probability) in names.iter().zip(probabilities.axis_iter(Axis(1))) {
+                columns.push(Column::new(format!("{PROBABILITY_PREFIX}{name}").into(), probability.to_vec()));
+            }
+        }
+    }
+
+    for column in columns {
+        if df.column(column.name()).is_ok() {
+            return Err(PipelineError::InvalidDataset {
+                path: input.to_string(),
+                message: format!("input already has a {} column, which predictions are written to", column.name())
+            });
+        }
+        let at = if column.name() == ROW_ID_COLUMN { 0 } else { df.width() };
+        df.insert_column(at, column).map_err(|err| PipelineError::InvalidDataset {
+            path: input.to_string(),
+            message: err.to_string()
+        })?;
+    }
+
+    write_table(&output, &mut df)?;
+    Ok(BatchPredictions { rows: df.height(), output })
+}
+
+
+/// `<dir>/<stem>_predictions.<ext>` next to an input file
+fn default_output(input: &str) -> String {
+    let path = Path::new(input);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().map(|ext| ext.to_string_lossy().to_string()).unwrap_or("parquet".to_string());
+    path.with_file_name(format!("{stem}_predictions.{extension}")).to_string_lossy().to_string()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::tests::exported;
+
+    /// Fresh path for a test file, removing any left by an earlier run
+    fn path(name: &str) -> String {
+        let dir = std::env::temp_dir().join("crate_batch_tests");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        let _ = std::fs::remove_file(&path);
+        path.to_string_lossy().into_owned()
+    }
+
+    /// Rows with the features out of order and a column the model does not use
+    fn write_rows(name: &str) -> String {
+        let input = path(name);
+        let mut df = df!(
+            "b" => [2.0, 1.0, 0.5],
+            "note" => ["first", "second", "third"],
+            "a" => [1.0, 4.0, 0.5]
+        ).unwrap();
+        write_table(&input, &mut df).unwrap();
+        input
+    }
+
+    fn column_names(df: &DataFrame) -> Vec<&str> {
+        df.get_column_names().iter().map(|name| name.as_str()).collect()
+    }
+
+    #[test]
+    fn writes_classified_rows() {
+        let input = write_rows("binary.csv");
+        let output = path("binary_scored.csv");
+
+        let scored = predict_file(&exported(Task::Binary), &input, Some(&output)).unwrap();
+        assert_eq!(scored, BatchPredictions { rows: 3, output: output.clone() });
+
+        let written = read_table(&output).unwrap();
+        assert_eq!(
+            column_names(&written),
+            ["row_id", "b", "note", "a", "prediction", "label", "probability_no", "probability_yes"]
+        );
+        let labels: Vec<_> = written.column("label").unwrap().str().unwrap().into_no_null_iter().collect();
+        assert_eq!(labels, ["no", "yes", "no"]);
+
+        let found = select_array(&written, &output, &["probability_yes".to_string(), "prediction".to_string()]).unwrap();
+        for (row, logit) in found.outer_iter().zip([-1.0f64, 3.0, 0.0]) {
+            assert!((row[0] - 1.0 / (1.0 + (-logit).exp())).abs() < 1e-12);
+        }
+        assert_eq!(found.column(1).to_vec(), [0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn writes_regression_rows_next_to_parquet_input() {
+        let input = write_rows("regression.parquet");
+        let output = default_output(&input);
+        let _ = std::fs::remove_file(&output);
+        assert!(output.ends_with("regression_predictions.parquet"));
+
+        let scored = predict_file(&exported(Task::Regression), &input, None).unwrap();
+        assert_eq!(scored.output, output);
+
+        let written = read_table(&output).unwrap();
+        assert_eq!(column_names(&written), ["row_id", "b", "note", "a", "prediction"]);
+        let found = select_array(&written, &output, &["row_id".to_string(), "prediction".to_string()]).unwrap();
+        assert_eq!(found.column(0).to_vec(), [0.0, 1.0, 2.0]);
+        assert_eq!(found.column(1).to_vec(), [3.0, 10.0, 3.5]);
+    }
+
+    #[test]
+    fn reports_missing_and_null_features() {
+        let model = exported(Task::Multiclass);
+        let input = path("missing.csv");
+        let output = path("missing_scored.csv");
+
+        let mut df = df!("a" => [1.0, 2.0]).unwrap();
+        write_table(&input, &mut df).unwrap();
+        match predict_file(&model, &input, Some(&output)) {
+            Err(PipelineError::MissingColumn { column, .. }) => assert_eq!(column, "b"),
+            other => panic!("expected missing column b, found {other:?}")
+        }
+
+        let mut df = df!("a" => [Some(1.0), None], "b" => [2.0, 1.0]).unwrap();
+        write_table(&input, &mut df).unwrap();
+        let err = predict_file(&model, &input, Some(&output)).unwrap_err();
+        assert_eq!(err.kind(), "invalid_dataset");
+        assert!(err.to_string().contains("feature column a has 1 missing values"), "{err}");
+        assert!(!Path::new(&output).exists());
+    }
+
+    #[test]
+    fn refuses_to_overwrite_input_or_prediction_columns() {
+        let model = exported(Task::Regression);
+        let input = write_rows("overwrite.csv");
+        let err = predict_file(&model, &input, Some(&input)).unwrap_err();
+        assert_eq!(err.kind(), "config");
+
+        let mut df = df!("a" => [1.0], "b" => [2.0], "prediction" => [0.0]).unwrap();
+        write_table(&input, &mut df).unwrap();
+        let err = predict_file(&model, &input, Some(&path("overwrite_scored.csv"))).unwrap_err();
+        assert_eq!(err.kind(), "invalid_dataset");
+        assert!(err.to_string().contains("already has a prediction column"), "{err}");
+    }
+
+}
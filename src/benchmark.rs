@@ -0,0 +1,440 @@
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+use serde::{Serialize, Deserialize};
+
+use crate::Task;
+use crate::artifact::{read_json, write_json};
+use crate::config::ExperimentConfig;
+use crate::error::*;
+use crate::evaluation::{Evaluation, Metrics};
+use crate::metrics::{headline_metrics, lower_is_better};
+use crate::pipeline::{Pipeline, Stage};
+use crate::preprocessing::PREPROCESSING_FILE;
+use crate::store::{ArtifactStore, VersionSpec, GRAPH_DIR, PARAMETERS_FILE};
+
+
+/// Directory benchmark runs and the leaderboard are written to by default
+pub const BENCHMARK_DIR: &str = "benchmarks";
+
+/// File the leaderboard is saved to as JSON
+pub const LEADERBOARD_FILE: &str = "leaderboard.json";
+
+/// File the Markdown leaderboard is saved to
+pub const LEADERBOARD_SUMMARY_FILE: &str = "leaderboard.md";
+
+/// Files and directories of a saved model counted as its artifact size
+const MODEL_ARTIFACTS: [&str; 3] = [PARAMETERS_FILE, PREPROCESSING_FILE, GRAPH_DIR];
+
+
+/// Best scoring baseline on the leaderboard's ranking metric
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BaselineSummary {
+
+    /// Name of baseline
+    pub baseline: String,
+
+    /// Metric the baselines were compared on
+    pub metric: String,
+
+    /// Score of the baseline
+    pub value: f64
+}
+
+
+/// Outcome of running one pipeline end to end
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkResult {
+
+    /// Name the pipeline was targeted by
+    pub pipeline: String,
+
+    /// Name of type implementing the pipeline
+    pub model: String,
+
+    /// Prediction task of the model
+    pub task: Task,
+
+    /// Dataset file the pipeline reads
+    pub dataset: String,
+
+    /// Seed of the run
+    pub seed: u64,
+
+    /// Number of rows the model was scored on
+    pub test_rows: Option<usize>,
+
+    /// Wall time of the train stage in seconds
+    pub train_seconds: Option<f64>,
+
+    /// Size of the saved model and preprocessing files in bytes
+    pub artifact_bytes: Option<u64>,
+
+    /// Held out metrics of the trained model
+    pub metrics: Option<Metrics>,
+
+    /// Best naive baseline on the same rows
+    pub best_baseline: Option<BaselineSummary>,
+
+    /// Stage and error the run failed with
+    pub error: Option<String>
+}
+
+
+impl BenchmarkResult {
+
+    /// Run the load, transform, train and evaluate stages of a pipeline.
+    ///
+    /// Failures are recorded on the result rather than returned so that
+    /// one broken pipeline does not stop the benchmark.
+    ///
+    /// # Arguments
+    ///
+    /// * `pipeline` - Name the pipeline was targeted by.
+    /// * `model` - Name of type implementing the pipeline.
+    /// * `config` - Experiment config the pipeline was built from.
+    /// * `stages` - Pipeline built from the config, or the error building it.
+    ///
+    pub fn run(pipeline: &str, model: &str, config: &ExperimentConfig, stages: PipelineResult<Box<dyn Pipeline>>) -> Self {
+
+        let mut result = BenchmarkResult {
+            pipeline: pipeline.to_string(),
+            model: model.to_string(),
+            task: config.task(),
+            dataset: config.dataset.path.clone(),
+            seed: config.seed,
+            test_rows: None,
+            train_seconds: None,
+            artifact_bytes: None,
+            metrics: None,
+            best_baseline: None,
+            error: None
+        };
+
+        let mut stages = match stages {
+            Ok(stages) => stages,
+            Err(err) => {
+                result.error = Some(format!("{} (setup): {err}", err.kind()));
+                return result;
+            }
+        };
+
+        for stage in [Stage::Load, Stage::Transform, Stage::Train, Stage::Evaluate] {
+            let start = Instant::now();
+            if let Err(err) = stages.run_stage(stage) {
+                result.error = Some(format!("{} ({}): {err}", err.kind(), stage.name()));
+                return result;
+            }
+            if stage == Stage::Train {
+                result.train_seconds = Some(start.elapsed().as_secs_f64());
+            }
+        }
+
+        let dir = match ArtifactStore::new(&config.output_dir).dir(VersionSpec::Latest) {
+            Ok(dir) => dir,
+            Err(err) => {
+                result.error = Some(format!("{} (evaluate): {err}", err.kind()));
+                return result;
+            }
+        };
+
+        match Evaluation::load(&dir) {
+            Ok(evaluation) => {
+                result.test_rows = Some(evaluation.rows);
+                result.best_baseline = best_baseline(&evaluation);
+                result.metrics = Some(evaluation.metrics);
+            },
+            Err(err) => result.error = Some(format!("{} (evaluate): {err}", err.kind()))
+        }
+        result.artifact_bytes = Some(MODEL_ARTIFACTS.iter().map(|file| disk_size(&Path::new(&dir).join(file))).sum());
+        result
+    }
+
+}
+
+
+/// Baseline scoring best on the first headline metric of the task, the first listed on ties
+fn best_baseline(evaluation: &Evaluation) -> Option<BaselineSummary> {
+
+    let metric = headline_metrics(evaluation.task)[0];
+    let lower = lower_is_better(metric);
+    evaluation.baselines
+        .iter()
+        .filter_map(|score| score.metrics.get(metric).map(|value| (score.baseline.name(), value)))
+        .filter(|(_, value)| value.is_finite())
+        .reduce(|best, next| {
+            let better = if lower { next.1 < best.1 } else { next.1 > best.1 };
+            if better { next } else { best }
+        })
+        .map(|(baseline, value)| BaselineSummary {
+            baseline: baseline.to_string(),
+            metric: metric.to_string(),
+            value
+        })
+}
+
+
+/// Size in bytes of a file, or every file below a directory
+fn disk_size(path: &Path) -> u64 {
+    match fs::read_dir(path) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| disk_size(&entry.path()))
+            .sum(),
+        Err(_) => fs::metadata(path).map(|meta| meta.len()).unwrap_or(0)
+    }
+}
+
+
+/// Results of a benchmark run over every targeted pipeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Leaderboard {
+
+    /// Version of this crate the benchmark ran with
+    pub crate_version: String,
+
+    /// Version of dendritic the benchmark ran with, if Cargo.lock was found at build time
+    pub dendritic_version: Option<String>,
+
+    /// Result of every pipeline in the order they were run
+    pub results: Vec<BenchmarkResult>
+}
+
+
+impl Leaderboard {
+
+    /// Leaderboard of results tagged with the crate and dendritic versions
+    pub fn new(results: Vec<BenchmarkResult>) -> Self {
+        Leaderboard {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            dendritic_version: dendritic_version(),
+            results
+        }
+    }
+
+    /// Whether any pipeline failed
+    pub fn has_failures(&self) -> bool {
+        self.results.iter().any(|result| result.error.is_some())
+    }
+
+    /// Save leaderboard as JSON and Markdown
+    pub fn save(&self, dir: &str) -> PipelineResult<()> {
+        write_json(dir, LEADERBOARD_FILE, self)?;
+        let path = format!("{dir}/{LEADERBOARD_SUMMARY_FILE}");
+        fs::write(&path, self.to_markdown()).map_err(|err| PipelineError::artifact(&path, err))
+    }
+
+    /// Load leaderboard from a benchmark directory
+    pub fn load(dir: &str) -> PipelineResult<Self> {
+        read_json(dir, LEADERBOARD_FILE)
+    }
+
+    /// Render leaderboard as Markdown tables, one per task
+    pub fn to_markdown(&self) -> String {
+
+        let mut md = String::new();
+        let _ = writeln!(md, "# Benchmark Leaderboard\n");
+        let _ = writeln!(
+            md,
+            "dendritic_ml_models {} | dendritic {}",
+            self.crate_version,
+            self.dendritic_version.as_deref().unwrap_or("unknown")
+        );
+
+        for (title, tasks) in [
+            ("Regression", vec![Task::Regression]),
+            ("Classification", vec![Task::Binary, Task::Multiclass])
+        ] {
+            let results: Vec<&BenchmarkResult> = self.results
+                .iter()
+                .filter(|result| result.error.is_none() && tasks.contains(&result.task))
+                .collect();
+            if results.is_empty() {
+                continue;
+            }
+
+            let headline = headline_metrics(tasks[0]);
+            let _ = writeln!(md, "\n## {title}\n");
+            let _ = writeln!(
+                md,
+                "| Pipeline | Model | Seed | Test rows | {} | Best baseline | Train (s) | Artifact (KB) |",
+                headline.join(" | ")
+            );
+            let _ = writeln!(md, "| --- | --- | --- | --- |{} --- | --- | --- |", " --- |".repeat(headline.len()));
+
+            for result in results {
+                let values: Vec<String> = headline
+                    .iter()
+                    .map(|metric| optional(result.metrics.as_ref().and_then(|metrics| metrics.get(metric)), 4))
+                    .collect();
+                let baseline = match &result.best_baseline {
+                    Some(best) => format!("{} ({} {:.4})", best.baseline, best.metric, best.value),
+                    None => "-".to_string()
+                };
+                let _ = writeln!(
+                    md,
+                    "| {} | {} | {} | {} | {} | {baseline} | {} | {} |",
+                    result.pipeline,
+                    result.model,
+                    result.seed,
+                    result.test_rows.map(|rows| rows.to_string()).unwrap_or("-".to_string()),
+                    values.join(" | "),
+                    optional(result.train_seconds, 2),
+                    optional(result.artifact_bytes.map(|bytes| bytes as f64 / 1024.0), 1)
+                );
+            }
+        }
+
+        let failed: Vec<&BenchmarkResult> = self.results.iter().filter(|result| result.error.is_some()).collect();
+        if !failed.is_empty() {
+            let _ = writeln!(md, "\n## Failed\n");
+            let _ = writeln!(md, "| Pipeline | Model | Error |");
+            let _ = writeln!(md, "| --- | --- | --- |");
+            for result in failed {
+                let error = result.error.as_deref().unwrap_or_default().replace('|', "\\|");
+                let _ = writeln!(md, "| {} | {} | {error} |", result.pipeline, result.model);
+            }
+        }
+
+        md
+    }
+
+}
+
+
+/// Version of dendritic pinned in Cargo.lock when the crate was built, see `build.rs`
+fn dendritic_version() -> Option<String> {
+    option_env!("DENDRITIC_VERSION").map(|version| version.to_string())
+}
+
+
+fn optional(value: Option<f64>, precision: usize) -> String {
+    match value {
+        Some(value) => format!("{value:.precision$}"),
+        None => "-".to_string()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use ndarray::array;
+
+    use super::*;
+    use crate::baseline::{Baseline, BaselineScore};
+    use crate::metrics::{ClassificationMetrics, RegressionMetrics};
+
+    fn regression(rmse: f64) -> Metrics {
+        Metrics::Regression(RegressionMetrics { mse: rmse * rmse, rmse, mae: rmse / 2.0, r2: 0.5, mape: 0.1 })
+    }
+
+    /// Classification metrics with the given accuracy over four rows
+    fn classification(correct: usize) -> Metrics {
+        let probabilities = array![[0.9, 0.1], [0.8, 0.2], [0.3, 0.7], [0.4, 0.6]];
+        let y_true: Vec<usize> = (0..4).map(|row| if row < correct { usize::from(row >= 2) } else { usize::from(row < 2) }).collect();
+        Metrics::Classification(ClassificationMetrics::compute(&y_true, &probabilities.view()))
+    }
+
+    fn evaluation(task: Task, baselines: Vec<(Baseline, Metrics)>) -> Evaluation {
+        Evaluation {
+            experiment: "bench".to_string(),
+            task,
+            partition: "test".to_string(),
+            rows: 4,
+            seed: 7,
+            metrics: baselines[0].1.clone(),
+            baselines: baselines
+                .into_iter()
+                .map(|(baseline, metrics)| BaselineScore { baseline, rows: 4, metrics })
+                .collect()
+        }
+    }
+
+    fn result(pipeline: &str, task: Task, metrics: Option<Metrics>, error: Option<&str>) -> BenchmarkResult {
+        BenchmarkResult {
+            pipeline: pipeline.to_string(),
+            model: "TabularPipeline<SGD>".to_string(),
+            task,
+            dataset: "data/bench.parquet".to_string(),
+            seed: 7,
+            test_rows: metrics.as_ref().map(|_| 4),
+            train_seconds: metrics.as_ref().map(|_| 1.25),
+            artifact_bytes: metrics.as_ref().map(|_| 2048),
+            best_baseline: metrics.as_ref().map(|_| BaselineSummary { baseline: "mean".to_string(), metric: "rmse".to_string(), value: 2.0 }),
+            metrics,
+            error: error.map(|error| error.to_string())
+        }
+    }
+
+    #[test]
+    fn best_baseline_follows_metric_direction() {
+        let lowest = evaluation(Task::Regression, vec![
+            (Baseline::Mean, regression(3.0)),
+            (Baseline::Median, regression(2.0)),
+            (Baseline::Persistence, regression(f64::NAN))
+        ]);
+        let best = best_baseline(&lowest).unwrap();
+        assert_eq!(best, BaselineSummary { baseline: "median".to_string(), metric: "rmse".to_string(), value: 2.0 });
+
+        let highest = evaluation(Task::Binary, vec![(Baseline::Majority, classification(2)), (Baseline::Stratified, classification(3))]);
+        let best = best_baseline(&highest).unwrap();
+        assert_eq!((best.baseline.as_str(), best.metric.as_str(), best.value), ("stratified", "accuracy", 0.75));
+
+        assert!(best_baseline(&Evaluation { baselines: Vec::new(), ..lowest }).is_none());
+    }
+
+    #[test]
+    fn best_baseline_keeps_the_first_on_ties() {
+        let lower = evaluation(Task::Regression, vec![(Baseline::Mean, regression(2.0)), (Baseline::Median, regression(2.0))]);
+        assert_eq!(best_baseline(&lower).unwrap().baseline, "mean");
+
+        let higher = evaluation(Task::Binary, vec![(Baseline::Majority, classification(2)), (Baseline::Stratified, classification(2))]);
+        assert_eq!(best_baseline(&higher).unwrap().baseline, "majority");
+    }
+
+    #[test]
+    fn leaderboard_lists_results_by_task_and_failures() {
+        let leaderboard = Leaderboard::new(vec![
+            result("housing", Task::Regression, Some(regression(2.5)), None),
+            result("iris", Task::Multiclass, Some(classification(3)), None),
+            result("broken", Task::Binary, None, Some("config (setup): a | b"))
+        ]);
+        assert!(leaderboard.has_failures());
+        assert_eq!(leaderboard.crate_version, env!("CARGO_PKG_VERSION"));
+
+        let md = leaderboard.to_markdown();
+        let regression = md.find("## Regression").unwrap();
+        let classification = md.find("## Classification").unwrap();
+        let failed = md.find("## Failed").unwrap();
+        assert!(regression < classification && classification < failed, "{md}");
+        assert!(md.contains("| housing | TabularPipeline<SGD> | 7 | 4 | 2.5000 | 1.2500 | 0.5000 | mean (rmse 2.0000) | 1.25 | 2.0 |"), "{md}");
+        assert!(md.contains("| iris | TabularPipeline<SGD> | 7 | 4 | 0.7500 |"), "{md}");
+
+        // Failed pipelines are only listed in the failed table, with pipes escaped
+        assert!(md[failed..].contains("| broken | TabularPipeline<SGD> | config (setup): a \\| b |"), "{md}");
+        assert!(!md[..failed].contains("broken"), "{md}");
+
+        let passing = Leaderboard::new(leaderboard.results[..1].to_vec());
+        assert!(!passing.has_failures() && !passing.to_markdown().contains("## Classification"));
+        assert!(!passing.to_markdown().contains("## Failed"));
+    }
+
+    #[test]
+    fn leaderboard_round_trips() {
+        let dir = std::env::temp_dir().join("crate_benchmark_tests/leaderboard");
+        let dir = dir.to_string_lossy();
+        let _ = fs::remove_dir_all(&*dir);
+
+        let leaderboard = Leaderboard::new(vec![
+            result("housing", Task::Regression, Some(regression(2.5)), None),
+            result("broken", Task::Binary, None, Some("missing_file (load): data/bench.parquet"))
+        ]);
+        leaderboard.save(&dir).unwrap();
+        assert_eq!(Leaderboard::load(&dir).unwrap(), leaderboard);
+        assert_eq!(fs::read_to_string(format!("{dir}/{LEADERBOARD_SUMMARY_FILE}")).unwrap(), leaderboard.to_markdown());
+    }
+
+}
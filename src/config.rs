@@ -0,0 +1,681 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Serialize, Deserialize};
+
+use crate::Task;
+use crate::store::VersionSpec;
+
+
+/// Directory experiment config files are discovered in
+pub const EXPERIMENTS_DIR: &str = "experiments";
+
+/// Seed used by runs that do not set one
+pub const DEFAULT_SEED: u64 = 42;
+
+
+/// Declarative description of a single tabular experiment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExperimentConfig {
+
+    /// Name of experiment
+    pub name: String,
+
+    /// Artifact store directory, each training run saves a new version below it
+    pub output_dir: String,
+
+    /// Saved version evaluated and used for inference
+    #[serde(default)]
+    pub model_version: VersionSpec,
+
+    /// Dataset the experiment reads
+    pub dataset: DatasetConfig,
+
+    /// Preprocessing applied to features and target
+    #[serde(default)]
+    pub transforms: TransformConfig,
+
+    /// Model to train
+    pub model: ModelConfig,
+
+    /// Training loop settings
+    #[serde(default)]
+    pub hyperparameters: HyperparameterConfig,
+
+    /// Seed for split shuffling and mini-batch ordering
+    #[serde(default = "default_seed")]
+    pub seed: u64,
+
+    /// Cross validation settings
+    #[serde(default)]
+    pub cross_validation: CrossValidationConfig,
+
+    /// Hyperparameter search space
+    #[serde(default)]
+    pub search: SearchConfig,
+
+    /// Validation loss monitoring, training runs every epoch when unset
+    #[serde(default)]
+    pub early_stopping: Option<EarlyStoppingConfig>
+}
+
+
+/// Dataset location and columns used by an experiment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DatasetConfig {
+
+    /// Path of parquet or CSV file with training data
+    pub path: String,
+
+    /// Optional parquet or CSV file with held out test data
+    #[serde(default)]
+    pub test_path: Option<String>,
+
+    /// Feature columns to select
+    pub features: Vec<String>,
+
+    /// Target column to predict
+    pub target: String,
+
+    /// Fraction of rows held out for testing when no test file is given
+    #[serde(default = "default_test_size")]
+    pub test_size: f64,
+
+    /// Fraction of rows held out for validation
+    #[serde(default)]
+    pub validation_size: f64,
+
+    /// Strategy used to assign rows to partitions
+    #[serde(default)]
+    pub split: SplitKind,
+
+    /// Column identifying groups kept within a single partition (grouped split only)
+    #[serde(default)]
+    pub group: Option<String>,
+
+    /// Rows in one seasonal cycle, enables the seasonal naive baseline (chronological split only)
+    #[serde(default)]
+    pub season: Option<usize>,
+
+    /// Column holding the class name of every target code (classification only)
+    #[serde(default)]
+    pub labels: Option<String>
+}
+
+
+/// Strategies for splitting rows into train, validation and test partitions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitKind {
+
+    /// Shuffle rows before partitioning
+    Random,
+
+    /// Shuffle rows within each class so partitions keep class proportions
+    Stratified,
+
+    /// Shuffle groups so every row of a group lands in one partition
+    Grouped,
+
+    /// Keep file order, earliest rows train and latest rows test
+    #[default]
+    Chronological
+}
+
+
+/// Encoders applied to feature and target columns
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransformConfig {
+
+    /// Encoder applied to feature columns
+    #[serde(default)]
+    pub features: EncoderKind,
+
+    /// Encoder applied to target column
+    #[serde(default)]
+    pub target: EncoderKind
+}
+
+
+/// Supported feature encoders
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EncoderKind {
+    #[default]
+    None,
+    Standard,
+    MinMax,
+    OneHot
+}
+
+
+/// Supported model kinds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelKind {
+    Sgd,
+    Logistic
+}
+
+
+/// Supported optimizers for parameter updates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OptimizerKind {
+    #[default]
+    Default,
+    Adam,
+    Nesterov,
+    Adagrad,
+    RmsProp,
+    Adadelta
+}
+
+
+/// Model construction settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ModelConfig {
+
+    /// Kind of model to train
+    pub kind: ModelKind,
+
+    /// Flag for multi class classification (logistic only)
+    #[serde(default)]
+    pub multi_class: bool,
+
+    /// Learning rate of model
+    pub learning_rate: f64,
+
+    /// Optimizer used for parameter updates
+    #[serde(default)]
+    pub optimizer: OptimizerKind
+}
+
+
+/// Arguments passed to batch training
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HyperparameterConfig {
+
+    /// Number of training iterations
+    #[serde(default = "default_iterations")]
+    pub iterations: usize,
+
+    /// Number of rows in each batch
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+
+    /// Number of epochs trained within each iteration
+    #[serde(default = "default_batch_epochs")]
+    pub batch_epochs: usize
+}
+
+
+/// Stop training once validation loss stops improving.
+///
+/// Validation loss is measured every `eval_every` epochs. Training stops
+/// after `patience` measurements without an improvement of at least
+/// `min_delta`, and the parameters with the lowest validation loss are
+/// restored before the model is saved.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EarlyStoppingConfig {
+
+    /// Number of epochs between validation loss measurements
+    #[serde(default = "default_eval_every")]
+    pub eval_every: usize,
+
+    /// Number of measurements without improvement before stopping
+    #[serde(default = "default_patience")]
+    pub patience: usize,
+
+    /// Smallest decrease in validation loss counted as an improvement
+    #[serde(default)]
+    pub min_delta: f64
+}
+
+
+impl Default for EarlyStoppingConfig {
+
+    fn default() -> Self {
+        EarlyStoppingConfig {
+            eval_every: default_eval_every(),
+            patience: default_patience(),
+            min_delta: 0.0
+        }
+    }
+
+}
+
+
+/// Settings of the cross validation runner
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CrossValidationConfig {
+
+    /// Number of folds
+    #[serde(default = "default_folds")]
+    pub folds: usize,
+
+    /// Number of times folds are redrawn
+    #[serde(default = "default_repeats")]
+    pub repeats: usize
+}
+
+
+impl Default for CrossValidationConfig {
+
+    fn default() -> Self {
+        CrossValidationConfig {
+            folds: default_folds(),
+            repeats: default_repeats()
+        }
+    }
+
+}
+
+
+/// Hyperparameter search space and how candidates are selected.
+///
+/// Empty lists keep the value of the experiment config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SearchConfig {
+
+    /// How candidates are drawn from the space
+    #[serde(default)]
+    pub strategy: SearchStrategy,
+
+    /// Number of candidates drawn by random search
+    #[serde(default = "default_trials")]
+    pub trials: usize,
+
+    /// How candidates are scored
+    #[serde(default)]
+    pub selection: Selection,
+
+    /// Metric candidates are ranked by (rmse or accuracy by default)
+    #[serde(default)]
+    pub metric: Option<String>,
+
+    /// Learning rates to try
+    #[serde(default)]
+    pub learning_rate: Vec<f64>,
+
+    /// Batch sizes to try
+    #[serde(default)]
+    pub batch_size: Vec<usize>,
+
+    /// Training iterations to try
+    #[serde(default)]
+    pub iterations: Vec<usize>,
+
+    /// Epochs per iteration to try
+    #[serde(default)]
+    pub batch_epochs: Vec<usize>,
+
+    /// Optimizers to try (sgd only)
+    #[serde(default)]
+    pub optimizer: Vec<OptimizerKind>
+}
+
+
+/// Strategies for drawing search candidates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchStrategy {
+
+    /// Every combination of the listed values
+    #[default]
+    Grid,
+
+    /// Random sample of `trials` combinations
+    Random
+}
+
+
+/// How search candidates are scored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Selection {
+
+    /// Train on the training partition, score on the validation partition
+    #[default]
+    Validation,
+
+    /// Mean score of k-fold cross validation over the non test rows
+    CrossValidation
+}
+
+
+impl Default for SearchConfig {
+
+    fn default() -> Self {
+        SearchConfig {
+            strategy: SearchStrategy::default(),
+            trials: default_trials(),
+            selection: Selection::default(),
+            metric: None,
+            learning_rate: Vec::new(),
+            batch_size: Vec::new(),
+            iterations: Vec::new(),
+            batch_epochs: Vec::new(),
+            optimizer: Vec::new()
+        }
+    }
+
+}
+
+
+impl Default for HyperparameterConfig {
+
+    fn default() -> Self {
+        HyperparameterConfig {
+            iterations: default_iterations(),
+            batch_size: default_batch_size(),
+            batch_epochs: default_batch_epochs()
+        }
+    }
+
+}
+
+
+fn default_seed() -> u64 { DEFAULT_SEED }
+
+fn default_test_size() -> f64 { 0.2 }
+
+fn default_iterations() -> usize { 10 }
+
+fn default_batch_size() -> usize { 32 }
+
+fn default_batch_epochs() -> usize { 100 }
+
+fn default_folds() -> usize { 5 }
+
+fn default_repeats() -> usize { 1 }
+
+fn default_trials() -> usize { 10 }
+
+fn default_eval_every() -> usize { 10 }
+
+fn default_patience() -> usize { 5 }
+
+
+impl ExperimentConfig {
+
+    /// Parse experiment config from TOML source.
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - Contents of TOML config file.
+    ///
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let config: ExperimentConfig = toml::from_str(source)
+            .map_err(|err| format!("Invalid experiment config: {err}"))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Read and parse experiment config from file
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .map_err(|err| format!("Unable to read {}: {err}", path.display()))?;
+        Self::parse(&source).map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    /// Override seed of config, keeping its own when none is given
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        if let Some(seed) = seed {
+            self.seed = seed;
+        }
+        self
+    }
+
+    /// Prediction task implied by the model settings
+    pub fn task(&self) -> Task {
+        match (self.model.kind, self.model.multi_class) {
+            (ModelKind::Sgd, _) => Task::Regression,
+            (ModelKind::Logistic, false) => Task::Binary,
+            (ModelKind::Logistic, true) => Task::Multiclass
+        }
+    }
+
+    /// Path of config file for experiment name in the experiments directory
+    pub fn path_for(name: &str) -> String {
+        format!("{EXPERIMENTS_DIR}/{name}.toml")
+    }
+
+    /// Names of every experiment config in the experiments directory
+    pub fn discover() -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(EXPERIMENTS_DIR)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+                    .filter_map(|path| path.file_stem().map(|s| s.to_string_lossy().to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort();
+        names
+    }
+
+    /// Metric search candidates are ranked by
+    pub fn search_metric(&self) -> String {
+        match (&self.search.metric, self.task()) {
+            (Some(metric), _) => metric.clone(),
+            (None, Task::Regression) => "rmse".to_string(),
+            (None, Task::Binary | Task::Multiclass) => "accuracy".to_string()
+        }
+    }
+
+    /// Check settings are consistent with each other
+    pub fn validate(&self) -> Result<(), String> {
+
+        if self.dataset.features.is_empty() {
+            return Err("dataset.features must list at least one column".to_string());
+        }
+
+        if !(0.0..1.0).contains(&self.dataset.test_size) {
+            return Err("dataset.test_size must be in [0, 1)".to_string());
+        }
+
+        if !(0.0..1.0).contains(&self.dataset.validation_size) {
+            return Err("dataset.validation_size must be in [0, 1)".to_string());
+        }
+
+        if self.dataset.test_path.is_none() && self.dataset.test_size + self.dataset.validation_size >= 1.0 {
+            return Err("dataset.test_size and dataset.validation_size must leave training rows".to_string());
+        }
+
+        if (self.dataset.split == SplitKind::Grouped) != self.dataset.group.is_some() {
+            return Err("dataset.group must be set for, and only for, grouped splits".to_string());
+        }
+
+        if self.dataset.season.is_some_and(|season| season == 0) {
+            return Err("dataset.season must be greater than 0".to_string());
+        }
+
+        if self.dataset.season.is_some() && self.dataset.split != SplitKind::Chronological {
+            return Err("dataset.season requires a chronological split".to_string());
+        }
+
+        if self.dataset.labels.is_some() && self.model.kind != ModelKind::Logistic {
+            return Err("dataset.labels requires a classification model".to_string());
+        }
+
+        if self.dataset.split == SplitKind::Stratified && self.model.kind != ModelKind::Logistic {
+            return Err("stratified splits require a classification model".to_string());
+        }
+
+        if self.transforms.features == EncoderKind::OneHot {
+            return Err("one_hot encoding is only supported for the target".to_string());
+        }
+
+        if self.model.kind == ModelKind::Logistic && self.model.optimizer != OptimizerKind::Default {
+            return Err("logistic models only support the default optimizer".to_string());
+        }
+
+        if self.model.multi_class && self.transforms.target != EncoderKind::OneHot {
+            return Err("multi_class models require one_hot target encoding".to_string());
+        }
+
+        if self.hyperparameters.batch_size == 0 {
+            return Err("hyperparameters.batch_size must be greater than 0".to_string());
+        }
+
+        if self.cross_validation.folds < 2 || self.cross_validation.repeats == 0 {
+            return Err("cross_validation needs at least 2 folds and 1 repeat".to_string());
+        }
+
+        if self.search.strategy == SearchStrategy::Random && self.search.trials == 0 {
+            return Err("search.trials must be greater than 0".to_string());
+        }
+
+        if let Some(early_stopping) = &self.early_stopping {
+            if self.dataset.validation_size == 0.0 {
+                return Err("early_stopping requires dataset.validation_size".to_string());
+            }
+            if early_stopping.eval_every == 0 || early_stopping.patience == 0 {
+                return Err("early_stopping.eval_every and early_stopping.patience must be greater than 0".to_string());
+            }
+            if early_stopping.min_delta < 0.0 {
+                return Err("early_stopping.min_delta must not be negative".to_string());
+            }
+        }
+
+        let metric = self.search_metric();
+        if !crate::metrics::metric_names(self.task()).contains(&metric.as_str()) {
+            return Err(format!("search.metric {metric:?} is not reported for {} models", self.task().name()));
+        }
+
+        Ok(())
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const MINIMAL: &str = r#"
+name = "diabetes"
+output_dir = "models/diabetes"
+
+[dataset]
+path = "data/diabetes.parquet"
+features = ["glucose", "bmi"]
+target = "outcome"
+
+[model]
+kind = "logistic"
+learning_rate = 0.01
+"#;
+
+    #[test]
+    fn parses_shipped_experiments() {
+        for name in ExperimentConfig::discover() {
+            let config = ExperimentConfig::from_file(ExperimentConfig::path_for(&name)).unwrap();
+            assert_eq!(config.name, name);
+        }
+    }
+
+    #[test]
+    fn fills_defaults() {
+        let config = ExperimentConfig::parse(MINIMAL).unwrap();
+        assert_eq!(config.model_version, VersionSpec::Latest);
+        assert_eq!(config.seed, DEFAULT_SEED);
+        assert_eq!(config.dataset.test_size, 0.2);
+        assert_eq!(config.dataset.split, SplitKind::Chronological);
+        assert_eq!(config.hyperparameters.batch_size, 32);
+        assert!(config.early_stopping.is_none());
+    }
+
+    #[test]
+    fn fills_section_defaults_and_derived_settings() {
+        let source = MINIMAL.replace("target = \"outcome\"", "target = \"outcome\"\nvalidation_size = 0.1")
+            + "\n[early_stopping]\npatience = 3\n";
+        let config = ExperimentConfig::parse(&source).unwrap();
+        assert_eq!(config.early_stopping, Some(EarlyStoppingConfig { eval_every: 10, patience: 3, min_delta: 0.0 }));
+        assert_eq!((config.cross_validation.folds, config.cross_validation.repeats), (5, 1));
+        assert_eq!((config.task(), config.search_metric()), (Task::Binary, "accuracy".to_string()));
+
+        let multiclass = MINIMAL.replace("kind = \"logistic\"", "kind = \"logistic\"\nmulti_class = true")
+            + "\n[transforms]\ntarget = \"one_hot\"\n";
+        assert_eq!(ExperimentConfig::parse(&multiclass).unwrap().task(), Task::Multiclass);
+
+        let regression = ExperimentConfig::parse(&MINIMAL.replace("\"logistic\"", "\"sgd\"")).unwrap();
+        assert_eq!((regression.task(), regression.search_metric()), (Task::Regression, "rmse".to_string()));
+    }
+
+    #[test]
+    fn rejects_inconsistent_settings() {
+        let dataset = |line: &str| MINIMAL.replace("target = \"outcome\"", &format!("target = \"outcome\"\n{line}"));
+        let model = |line: &str| MINIMAL.replace("learning_rate = 0.01", &format!("learning_rate = 0.01\n{line}"));
+        let sgd = |line: &str| MINIMAL.replace("kind = \"logistic\"", &format!("kind = \"sgd\"\n{line}"));
+        let section = |lines: &str| format!("{MINIMAL}\n{lines}\n");
+
+        for (source, expected) in [
+            (MINIMAL.replace(r#"["glucose", "bmi"]"#, "[]"), "dataset.features must list at least one column"),
+            (dataset("test_size = 1.0"), "dataset.test_size must be in [0, 1)"),
+            (dataset("validation_size = -0.1"), "dataset.validation_size must be in [0, 1)"),
+            (dataset("test_size = 0.5\nvalidation_size = 0.5"), "must leave training rows"),
+            (dataset("split = \"grouped\""), "dataset.group must be set for, and only for, grouped splits"),
+            (dataset("group = \"clinic\""), "dataset.group must be set for, and only for, grouped splits"),
+            (dataset("season = 0"), "dataset.season must be greater than 0"),
+            (dataset("split = \"random\"\nseason = 7"), "dataset.season requires a chronological split"),
+            (sgd("").replace("target = \"outcome\"", "target = \"outcome\"\nlabels = \"diagnosis\""), "dataset.labels requires a classification model"),
+            (sgd("").replace("target = \"outcome\"", "target = \"outcome\"\nsplit = \"stratified\""), "stratified splits require a classification model"),
+            (section("[transforms]\nfeatures = \"one_hot\""), "one_hot encoding is only supported for the target"),
+            (model("optimizer = \"adam\""), "logistic models only support the default optimizer"),
+            (model("multi_class = true"), "multi_class models require one_hot target encoding"),
+            (section("[hyperparameters]\nbatch_size = 0"), "hyperparameters.batch_size must be greater than 0"),
+            (section("[cross_validation]\nfolds = 1"), "cross_validation needs at least 2 folds and 1 repeat"),
+            (section("[search]\nstrategy = \"random\"\ntrials = 0"), "search.trials must be greater than 0"),
+            (section("[early_stopping]\npatience = 3"), "early_stopping requires dataset.validation_size"),
+            (dataset("validation_size = 0.1") + "\n[early_stopping]\neval_every = 0\n", "must be greater than 0"),
+            (dataset("validation_size = 0.1") + "\n[early_stopping]\nmin_delta = -1.0\n", "early_stopping.min_delta must not be negative"),
+            (section("[search]\nmetric = \"rmse\""), "search.metric \"rmse\" is not reported for binary models")
+        ] {
+            let err = ExperimentConfig::parse(&source).unwrap_err();
+            assert!(err.contains(expected), "{expected:?} not in {err:?}\n{source}");
+        }
+
+        // Optimizers other than the default are accepted for SGD models
+        let sgd_adam = sgd("optimizer = \"adam\"");
+        assert!(ExperimentConfig::parse(&sgd_adam).is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        for (from, to) in [
+            ("name = \"diabetes\"", "name = \"diabetes\"\nepochs = 3"),
+            ("[model]", "[model]\nmomentum = 0.9"),
+            ("[model]", "[modle]\nkind = \"sgd\"\n[model]")
+        ] {
+            let err = ExperimentConfig::parse(&MINIMAL.replace(from, to)).unwrap_err();
+            assert!(err.starts_with("Invalid experiment config") && err.contains("unknown field"), "{err}");
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_toml() {
+        for (from, to) in [
+            ("name = \"diabetes\"", "name = \"diabetes"),
+            ("name = \"diabetes\"", "name = \"diabetes\"\nname = \"again\""),
+            (r#"features = ["glucose", "bmi"]"#, r#"features = ["glucose", "bmi""#)
+        ] {
+            let err = ExperimentConfig::parse(&MINIMAL.replace(from, to)).unwrap_err();
+            assert!(err.starts_with("Invalid experiment config"), "{err}");
+        }
+    }
+
+}
@@ -0,0 +1,266 @@
+use std::fmt::Write;
+use std::fs;
+
+use serde::{Serialize, Deserialize};
+
+use crate::Task;
+use crate::artifact::{read_json, write_json};
+use crate::config::SplitKind;
+use crate::error::*;
+use crate::evaluation::Metrics;
+use crate::metrics::headline_metrics;
+
+
+/// File cross validation results are saved to
+pub const CROSS_VALIDATION_FILE: &str = "cross_validation.json";
+
+/// File the Markdown cross validation summary is saved to
+pub const CROSS_VALIDATION_SUMMARY_FILE: &str = "cross_validation.md";
+
+
+/// Metrics of the model fit for a single fold
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FoldResult {
+
+    /// Repeat the fold belongs to, starting at 0
+    pub repeat: usize,
+
+    /// Index of fold within its repeat
+    pub fold: usize,
+
+    /// Number of rows the fold model was trained on
+    pub train_rows: usize,
+
+    /// Number of rows the fold model was scored on
+    pub validation_rows: usize,
+
+    /// Metrics on the validation rows of the fold
+    pub metrics: Metrics
+}
+
+
+/// Mean and population standard deviation of a metric over folds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricSummary {
+
+    /// Name of metric
+    pub metric: String,
+
+    /// Mean over folds
+    pub mean: f64,
+
+    /// Standard deviation over folds
+    pub std: f64,
+
+    /// Number of folds the metric was defined for
+    pub folds: usize
+}
+
+
+/// Results of a (repeated) k-fold cross validation run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrossValidation {
+
+    /// Name of experiment cross validated
+    pub experiment: String,
+
+    /// Prediction task of the model
+    pub task: Task,
+
+    /// Strategy used to assign rows to folds
+    pub strategy: SplitKind,
+
+    /// Number of folds per repeat
+    pub folds: usize,
+
+    /// Number of times folds were redrawn
+    pub repeats: usize,
+
+    /// Seed of the run
+    pub seed: u64,
+
+    /// Metrics of every fold
+    pub results: Vec<FoldResult>,
+
+    /// Mean and standard deviation of every metric
+    pub summary: Vec<MetricSummary>
+}
+
+
+impl CrossValidation {
+
+    /// Summarize fold results into mean and standard deviation per metric
+    pub fn summarize(results: &[FoldResult]) -> Vec<MetricSummary> {
+
+        let mut summary: Vec<(String, Vec<f64>)> = Vec::new();
+        for result in results {
+            for (metric, value) in result.metrics.scalars() {
+                match summary.iter_mut().find(|(name, _)| *name == metric) {
+                    Some((_, values)) => values.push(value),
+                    None => summary.push((metric, vec![value]))
+                }
+            }
+        }
+
+        summary
+            .into_iter()
+            .map(|(metric, values)| {
+                let count = values.len() as f64;
+                let mean = values.iter().sum::<f64>() / count;
+                let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count;
+                MetricSummary { metric, mean, std: variance.sqrt(), folds: values.len() }
+            })
+            .collect()
+    }
+
+    /// Summary of a metric by name
+    pub fn metric(&self, name: &str) -> Option<&MetricSummary> {
+        self.summary.iter().find(|summary| summary.metric == name)
+    }
+
+    /// Save results as JSON and Markdown to model artifact directory
+    pub fn save(&self, dir: &str) -> PipelineResult<()> {
+        write_json(dir, CROSS_VALIDATION_FILE, self)?;
+        let path = format!("{dir}/{CROSS_VALIDATION_SUMMARY_FILE}");
+        fs::write(&path, self.to_markdown()).map_err(|err| PipelineError::artifact(&path, err))
+    }
+
+    /// Load results from model artifact directory
+    pub fn load(dir: &str) -> PipelineResult<Self> {
+        read_json(dir, CROSS_VALIDATION_FILE)
+    }
+
+    /// Render results as a Markdown summary
+    pub fn to_markdown(&self) -> String {
+
+        let mut md = String::new();
+        let _ = writeln!(md, "# Cross Validation: {}\n", self.experiment);
+        let _ = writeln!(
+            md,
+            "Task: {} | Folds: {} x {} repeats ({:?}) | Seed: {}\n",
+            self.task.name(), self.folds, self.repeats, self.strategy, self.seed
+        );
+
+        let _ = writeln!(md, "| Metric | Mean ± Std |");
+        let _ = writeln!(md, "| ------ | ---------- |");
+        for summary in &self.summary {
+            let _ = writeln!(md, "| {} | {:.4} ± {:.4} |", summary.metric, summary.mean, summary.std);
+        }
+
+        // Per fold table covers the headline metrics only
+        let headline = headline_metrics(self.task);
+
+        let _ = writeln!(md, "\n| Repeat | Fold | Train | Validation | {} |", headline.join(" | "));
+        let _ = writeln!(md, "| --- | --- | --- | --- |{}", " --- |".repeat(headline.len()));
+        for result in &self.results {
+            let values: Vec<String> = headline
+                .iter()
+                .map(|metric| match result.metrics.get(metric) {
+                    Some(value) => format!("{value:.4}"),
+                    None => "-".to_string()
+                })
+                .collect();
+            let _ = writeln!(
+                md,
+                "| {} | {} | {} | {} | {} |",
+                result.repeat, result.fold, result.train_rows, result.validation_rows, values.join(" | ")
+            );
+        }
+
+        md
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use ndarray::array;
+
+    use super::*;
+    use crate::metrics::{ClassificationMetrics, RegressionMetrics};
+
+    fn regression(fold: usize, mse: f64) -> FoldResult {
+        FoldResult {
+            repeat: fold / 2,
+            fold: fold % 2,
+            train_rows: 8,
+            validation_rows: 2,
+            metrics: Metrics::Regression(RegressionMetrics { mse, rmse: mse.sqrt(), mae: mse / 2.0, r2: 1.0 - mse, mape: 0.1 })
+        }
+    }
+
+    fn results(summary: Vec<MetricSummary>, results: Vec<FoldResult>) -> CrossValidation {
+        CrossValidation {
+            experiment: "folds".to_string(),
+            task: Task::Regression,
+            strategy: SplitKind::Random,
+            folds: 2,
+            repeats: 2,
+            seed: 7,
+            summary,
+            results
+        }
+    }
+
+    #[test]
+    fn summarizes_mean_and_population_std() {
+        let folds: Vec<FoldResult> = [1.0, 2.0, 3.0, 4.0].into_iter().enumerate().map(|(fold, mse)| regression(fold, mse)).collect();
+        let summary = CrossValidation::summarize(&folds);
+
+        let names: Vec<&str> = summary.iter().map(|summary| summary.metric.as_str()).collect();
+        assert_eq!(names, ["mse", "rmse", "mae", "r2", "mape"]);
+
+        let mse = &summary[0];
+        assert_eq!((mse.mean, mse.folds), (2.5, 4));
+        assert!((mse.std - 1.25f64.sqrt()).abs() < 1e-12);
+
+        let mape = &summary[4];
+        assert!((mape.mean - 0.1).abs() < 1e-12 && mape.std < 1e-12);
+    }
+
+    #[test]
+    fn summarizes_metrics_only_over_folds_defining_them() {
+        // The second fold holds a single class, so its curve metrics are undefined
+        let both = ClassificationMetrics::compute(&[0, 1, 1, 0], &array![[0.2], [0.9], [0.6], [0.7]].view());
+        let single = ClassificationMetrics::compute(&[1, 1], &array![[0.8], [0.4]].view());
+        assert!(both.roc_auc.is_some() && single.roc_auc.is_none());
+
+        let folds: Vec<FoldResult> = [both.clone(), single.clone()]
+            .into_iter()
+            .enumerate()
+            .map(|(fold, metrics)| FoldResult { repeat: 0, fold, train_rows: 4, validation_rows: 4, metrics: Metrics::Classification(metrics) })
+            .collect();
+        let cv = results(CrossValidation::summarize(&folds), folds);
+
+        let roc_auc = cv.metric("roc_auc").unwrap();
+        assert_eq!((roc_auc.folds, roc_auc.mean, roc_auc.std), (1, both.roc_auc.unwrap(), 0.0));
+
+        let accuracy = cv.metric("accuracy").unwrap();
+        assert_eq!(accuracy.folds, 2);
+        assert!((accuracy.mean - (both.accuracy + single.accuracy) / 2.0).abs() < 1e-12);
+        assert!(cv.metric("mse").is_none());
+    }
+
+    #[test]
+    fn summarizes_no_folds_to_nothing() {
+        assert!(CrossValidation::summarize(&[]).is_empty());
+    }
+
+    #[test]
+    fn reports_every_fold() {
+        let folds: Vec<FoldResult> = [1.0, 2.0, 3.0, 4.0].into_iter().enumerate().map(|(fold, mse)| regression(fold, mse)).collect();
+        let cv = results(CrossValidation::summarize(&folds), folds);
+        let md = cv.to_markdown();
+        assert!(md.contains("| mse | 2.5000 ± 1.1180 |"), "{md}");
+        assert!(md.contains("| 1 | 1 | 8 | 2 | 2.0000 |"), "{md}");
+
+        let dir = std::env::temp_dir().join("crate_cross_validation_tests");
+        std::fs::create_dir_all(&dir).unwrap();
+        let dir = dir.to_string_lossy();
+        cv.save(&dir).unwrap();
+        assert_eq!(CrossValidation::load(&dir).unwrap(), cv);
+    }
+
+}
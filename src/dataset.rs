@@ -0,0 +1,187 @@
+use std::path::Path;
+
+use ndarray::{Array2, ArrayView1};
+use polars::prelude::*;
+use polars::prelude::ParquetReader;
+
+use crate::error::*;
+
+
+/// Read parquet file into a data frame.
+///
+/// # Arguments
+///
+/// * `path` - Path of parquet file.
+///
+pub fn read_parquet(path: &str) -> PipelineResult<DataFrame> {
+
+    if !Path::new(path).is_file() {
+        return Err(PipelineError::MissingFile { path: path.to_string() });
+    }
+
+    let mut file = std::fs::File::open(path)
+        .map_err(|err| PipelineError::artifact(path, err))?;
+
+    ParquetReader::new(&mut file)
+        .finish()
+        .map_err(|err| PipelineError::InvalidDataset {
+            path: path.to_string(),
+            message: err.to_string()
+        })
+}
+
+
+/// Read a parquet or CSV file (chosen by its `.csv` extension) into a data frame.
+///
+/// # Arguments
+///
+/// * `path` - Path of parquet or CSV file.
+///
+pub fn read_table(path: &str) -> PipelineResult<DataFrame> {
+
+    if !is_csv(path) {
+        return read_parquet(path);
+    }
+    if !Path::new(path).is_file() {
+        return Err(PipelineError::MissingFile { path: path.to_string() });
+    }
+
+    CsvReadOptions::default()
+        .with_has_header(true)
+        .try_into_reader_with_file_path(Some(path.into()))
+        .and_then(|reader| reader.finish())
+        .map_err(|err| PipelineError::InvalidDataset {
+            path: path.to_string(),
+            message: err.to_string()
+        })
+}
+
+
+/// Select numeric columns of data frame as a 2D array of floats.
+///
+/// # Arguments
+///
+/// * `df` - Data frame to select columns from.
+/// * `path` - Path the data frame was read from (for error reporting).
+/// * `columns` - Names of columns to select in order.
+///
+pub fn select_array(df: &DataFrame, path: &str, columns: &[String]) -> PipelineResult<Array2<f64>> {
+
+    for column in columns {
+        let series = df.column(column).map_err(|_| PipelineError::MissingColumn {
+            path: path.to_string(),
+            column: column.clone()
+        })?;
+
+        let dtype = series.dtype();
+        if !(dtype.is_primitive_numeric() || dtype.is_bool()) {
+            return Err(PipelineError::DtypeMismatch {
+                path: path.to_string(),
+                column: column.clone(),
+                dtype: dtype.to_string()
+            });
+        }
+    }
+
+    df.select(columns)
+        .and_then(|selected| selected.to_ndarray::<Float64Type>(IndexOrder::C))
+        .map_err(|err| PipelineError::InvalidDataset {
+            path: path.to_string(),
+            message: err.to_string()
+        })
+}
+
+
+/// Name of every class code from a column labelling the target.
+///
+/// # Arguments
+///
+/// * `df` - Data frame to read labels from.
+/// * `path` - Path the data frame was read from (for error reporting).
+/// * `codes` - Target class code of every row.
+/// * `column` - Name of string column holding the class name of every row.
+///
+/// Returns names indexed by class code. Codes without a row are named by
+/// their number.
+pub fn class_names(df: &DataFrame, path: &str, codes: &ArrayView1<f64>, column: &str) -> PipelineResult<Vec<String>> {
+
+    let series = df.column(column).map_err(|_| PipelineError::MissingColumn {
+        path: path.to_string(),
+        column: column.to_string()
+    })?;
+    let labels = series.str().map_err(|_| PipelineError::DtypeMismatch {
+        path: path.to_string(),
+        column: column.to_string(),
+        dtype: series.dtype().to_string()
+    })?;
+
+    let mut names: Vec<Option<String>> = Vec::new();
+    for (&code, label) in codes.iter().zip(labels) {
+        let (class, Some(label)) = (code as usize, label) else { continue };
+        if class >= names.len() {
+            names.resize(class + 1, None);
+        }
+        match &names[class] {
+            Some(name) if name != label => {
+                return Err(PipelineError::InvalidDataset {
+                    path: path.to_string(),
+                    message: format!("class {class} is labelled both {name:?} and {label:?} in {column}")
+                });
+            },
+            Some(_) => {},
+            None => names[class] = Some(label.to_string())
+        }
+    }
+
+    Ok(names
+        .into_iter()
+        .enumerate()
+        .map(|(class, name)| name.unwrap_or(class.to_string()))
+        .collect())
+}
+
+
+/// Write data frame to a parquet file.
+///
+/// # Arguments
+///
+/// * `path` - Path of parquet file, replaced if it exists.
+/// * `df` - Data frame to write.
+///
+pub fn write_parquet(path: &str, df: &mut DataFrame) -> PipelineResult<()> {
+
+    let mut file = std::fs::File::create(path)
+        .map_err(|err| PipelineError::artifact(path, err))?;
+
+    ParquetWriter::new(&mut file)
+        .finish(df)
+        .map(|_| ())
+        .map_err(|err| PipelineError::artifact(path, err))
+}
+
+
+/// Write data frame to a parquet or CSV file (chosen by its `.csv` extension).
+///
+/// # Arguments
+///
+/// * `path` - Path of file, replaced if it exists.
+/// * `df` - Data frame to write.
+///
+pub fn write_table(path: &str, df: &mut DataFrame) -> PipelineResult<()> {
+
+    if !is_csv(path) {
+        return write_parquet(path, df);
+    }
+
+    let mut file = std::fs::File::create(path)
+        .map_err(|err| PipelineError::artifact(path, err))?;
+
+    CsvWriter::new(&mut file)
+        .finish(df)
+        .map_err(|err| PipelineError::artifact(path, err))
+}
+
+
+fn is_csv(path: &str) -> bool {
+    Path::new(path).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+}
@@ -0,0 +1,146 @@
+use std::fmt;
+use std::path::Path;
+
+
+/// Errors raised by the stages of a model pipeline
+#[derive(Debug, Clone, PartialEq)]
+pub enum PipelineError {
+
+    /// Dataset or artifact file does not exist
+    MissingFile {
+        path: String
+    },
+
+    /// Column requested by the pipeline is not in the dataset
+    MissingColumn {
+        path: String,
+        column: String
+    },
+
+    /// Column cannot be converted to floating point values
+    DtypeMismatch {
+        path: String,
+        column: String,
+        dtype: String
+    },
+
+    /// Arrays do not have the dimensions a stage expects
+    ShapeMismatch {
+        context: String,
+        expected: String,
+        found: String
+    },
+
+    /// Model could not be constructed from the data and settings
+    ModelConstruction(String),
+
+    /// Model artifacts could not be written or read
+    ArtifactIo {
+        path: String,
+        message: String
+    },
+
+    /// Saved model files are inconsistent with each other or their manifest
+    InvalidArtifact {
+        path: String,
+        message: String
+    },
+
+    /// Dataset exists but could not be parsed
+    InvalidDataset {
+        path: String,
+        message: String
+    },
+
+    /// Experiment config is invalid
+    Config(String),
+
+    /// Stage was run before the stage it depends on
+    StageOrder(String),
+
+    /// Model registry change breaks a promotion rule or names no version
+    Registry(String)
+}
+
+
+/// Result type returned by fallible pipeline stages
+pub type PipelineResult<T> = Result<T, PipelineError>;
+
+
+impl PipelineError {
+
+    /// Short identifier of error kind used in reports
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PipelineError::MissingFile { .. } => "missing_file",
+            PipelineError::MissingColumn { .. } => "missing_column",
+            PipelineError::DtypeMismatch { .. } => "dtype_mismatch",
+            PipelineError::ShapeMismatch { .. } => "shape_mismatch",
+            PipelineError::ModelConstruction(_) => "model_construction",
+            PipelineError::ArtifactIo { .. } => "artifact_io",
+            PipelineError::InvalidArtifact { .. } => "invalid_artifact",
+            PipelineError::InvalidDataset { .. } => "invalid_dataset",
+            PipelineError::Config(_) => "config",
+            PipelineError::StageOrder(_) => "stage_order",
+            PipelineError::Registry(_) => "registry"
+        }
+    }
+
+    /// Map IO error on artifact path, treating not found as a missing file
+    pub fn artifact(path: &str, err: impl fmt::Display) -> Self {
+        if !Path::new(path).exists() {
+            return PipelineError::MissingFile { path: path.to_string() };
+        }
+        PipelineError::ArtifactIo {
+            path: path.to_string(),
+            message: err.to_string()
+        }
+    }
+
+}
+
+
+impl fmt::Display for PipelineError {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PipelineError::MissingFile { path } => {
+                write!(f, "File not found: {path}")
+            },
+            PipelineError::MissingColumn { path, column } => {
+                write!(f, "Column {column:?} not found in {path}")
+            },
+            PipelineError::DtypeMismatch { path, column, dtype } => {
+                write!(f, "Column {column:?} in {path} has non numeric type {dtype}")
+            },
+            PipelineError::ShapeMismatch { context, expected, found } => {
+                write!(f, "Shape mismatch in {context}: expected {expected}, found {found}")
+            },
+            PipelineError::ModelConstruction(msg) => {
+                write!(f, "Unable to construct model: {msg}")
+            },
+            PipelineError::ArtifactIo { path, message } => {
+                write!(f, "Artifact IO failed for {path}: {message}")
+            },
+            PipelineError::InvalidArtifact { path, message } => {
+                write!(f, "Invalid artifact {path}: {message}")
+            },
+            PipelineError::InvalidDataset { path, message } => {
+                write!(f, "Unable to read dataset {path}: {message}")
+            },
+            PipelineError::Config(msg) => {
+                write!(f, "Invalid config: {msg}")
+            },
+            PipelineError::StageOrder(msg) => {
+                write!(f, "Stage run out of order: {msg}")
+            },
+            PipelineError::Registry(msg) => {
+                write!(f, "Model registry: {msg}")
+            }
+        }
+    }
+
+}
+
+
+impl std::error::Error for PipelineError {}
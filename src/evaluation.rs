@@ -0,0 +1,206 @@
+use std::fmt::Write;
+use std::fs;
+
+use serde::{Serialize, Deserialize};
+
+use crate::Task;
+use crate::artifact::{read_json, write_json};
+use crate::baseline::BaselineScore;
+use crate::error::*;
+use crate::metrics::*;
+
+
+/// File machine readable evaluation results are saved to
+pub const EVALUATION_FILE: &str = "evaluation.json";
+
+/// File the Markdown evaluation summary is saved to
+pub const EVALUATION_SUMMARY_FILE: &str = "evaluation.md";
+
+
+/// Metrics of a model for the kind of task it solves
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Metrics {
+    Regression(RegressionMetrics),
+    Classification(ClassificationMetrics)
+}
+
+
+impl Metrics {
+
+    /// Named scalar metrics, skipping any that are undefined for the data
+    pub fn scalars(&self) -> Vec<(String, f64)> {
+        match self {
+            Metrics::Regression(metrics) => vec![
+                ("mse".to_string(), metrics.mse),
+                ("rmse".to_string(), metrics.rmse),
+                ("mae".to_string(), metrics.mae),
+                ("r2".to_string(), metrics.r2),
+                ("mape".to_string(), metrics.mape)
+            ],
+            Metrics::Classification(metrics) => {
+                let mut scalars = vec![
+                    ("accuracy".to_string(), Some(metrics.accuracy)),
+                    ("log_loss".to_string(), Some(metrics.log_loss)),
+                    ("roc_auc".to_string(), metrics.roc_auc),
+                    ("pr_auc".to_string(), metrics.pr_auc)
+                ];
+                for (name, score) in [
+                    ("precision", &metrics.precision),
+                    ("recall", &metrics.recall),
+                    ("f1", &metrics.f1)
+                ] {
+                    scalars.push((format!("{name}_binary"), score.binary));
+                    scalars.push((format!("{name}_macro"), Some(score.macro_avg)));
+                    scalars.push((format!("{name}_micro"), Some(score.micro_avg)));
+                    scalars.push((format!("{name}_weighted"), Some(score.weighted_avg)));
+                }
+                scalars
+                    .into_iter()
+                    .filter_map(|(name, value)| value.map(|value| (name, value)))
+                    .collect()
+            }
+        }
+    }
+
+    /// Look up a scalar metric by name
+    pub fn get(&self, name: &str) -> Option<f64> {
+        self.scalars()
+            .into_iter()
+            .find(|(metric, _)| metric == name)
+            .map(|(_, value)| value)
+    }
+
+}
+
+
+/// Held out evaluation of a trained model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Evaluation {
+
+    /// Name of experiment the model was trained by
+    pub experiment: String,
+
+    /// Prediction task of the model
+    pub task: Task,
+
+    /// Partition the metrics were computed on
+    pub partition: String,
+
+    /// Number of rows scored
+    pub rows: usize,
+
+    /// Seed of the run that produced the split
+    pub seed: u64,
+
+    /// Metrics computed on the partition
+    pub metrics: Metrics,
+
+    /// Metrics of naive baselines on the same partition
+    #[serde(default)]
+    pub baselines: Vec<BaselineScore>
+}
+
+
+impl Evaluation {
+
+    /// Save evaluation as JSON and Markdown to model artifact directory
+    pub fn save(&self, dir: &str) -> PipelineResult<()> {
+        write_json(dir, EVALUATION_FILE, self)?;
+        let path = format!("{dir}/{EVALUATION_SUMMARY_FILE}");
+        fs::write(&path, self.to_markdown()).map_err(|err| PipelineError::artifact(&path, err))
+    }
+
+    /// Load evaluation from model artifact directory
+    pub fn load(dir: &str) -> PipelineResult<Self> {
+        read_json(dir, EVALUATION_FILE)
+    }
+
+    /// Render evaluation as a Markdown summary
+    pub fn to_markdown(&self) -> String {
+
+        let mut md = String::new();
+        let _ = writeln!(md, "# Evaluation: {}\n", self.experiment);
+        let _ = writeln!(
+            md,
+            "Task: {} | Partition: {} ({} rows) | Seed: {}\n",
+            self.task.name(), self.partition, self.rows, self.seed
+        );
+
+        match &self.metrics {
+            Metrics::Regression(metrics) => {
+                let _ = writeln!(md, "| Metric | Value |");
+                let _ = writeln!(md, "| ------ | ----- |");
+                for (name, value) in [
+                    ("MSE", metrics.mse),
+                    ("RMSE", metrics.rmse),
+                    ("MAE", metrics.mae),
+                    ("R²", metrics.r2),
+                    ("MAPE", metrics.mape)
+                ] {
+                    let _ = writeln!(md, "| {name} | {value:.4} |");
+                }
+            },
+            Metrics::Classification(metrics) => {
+                let _ = writeln!(md, "| Metric | Value |");
+                let _ = writeln!(md, "| ------ | ----- |");
+                let _ = writeln!(md, "| Accuracy | {:.4} |", metrics.accuracy);
+                let _ = writeln!(md, "| Log loss | {:.4} |", metrics.log_loss);
+                let _ = writeln!(md, "| ROC-AUC | {} |", optional(metrics.roc_auc));
+                let _ = writeln!(md, "| PR-AUC | {} |", optional(metrics.pr_auc));
+
+                let _ = writeln!(md, "\n| Score | Binary | Macro | Micro | Weighted |");
+                let _ = writeln!(md, "| ----- | ------ | ----- | ----- | -------- |");
+                for (name, score) in [
+                    ("Precision", &metrics.precision),
+                    ("Recall", &metrics.recall),
+                    ("F1", &metrics.f1)
+                ] {
+                    let _ = writeln!(
+                        md,
+                        "| {name} | {} | {:.4} | {:.4} | {:.4} |",
+                        optional(score.binary), score.macro_avg, score.micro_avg, score.weighted_avg
+                    );
+                }
+
+                let counts = &metrics.confusion_matrix.counts;
+                let classes: Vec<String> = (0..counts.len()).map(|class| class.to_string()).collect();
+                let _ = writeln!(md, "\n### Confusion Matrix\n");
+                let _ = writeln!(md, "| Actual \\ Predicted | {} |", classes.join(" | "));
+                let _ = writeln!(md, "| --- |{}", " --- |".repeat(classes.len()));
+                for (class, row) in counts.iter().enumerate() {
+                    let row: Vec<String> = row.iter().map(|count| count.to_string()).collect();
+                    let _ = writeln!(md, "| {class} | {} |", row.join(" | "));
+                }
+            }
+        }
+
+        if !self.baselines.is_empty() {
+            let headline = headline_metrics(self.task);
+            let _ = writeln!(md, "\n### Baselines\n");
+            let _ = writeln!(md, "| Model | Rows | {} |", headline.join(" | "));
+            let _ = writeln!(md, "| --- | --- |{}", " --- |".repeat(headline.len()));
+
+            let rows = std::iter::once(("trained model", self.rows, &self.metrics))
+                .chain(self.baselines.iter().map(|score| (score.baseline.name(), score.rows, &score.metrics)));
+            for (name, rows, metrics) in rows {
+                let values: Vec<String> = headline
+                    .iter()
+                    .map(|metric| optional(metrics.get(metric)))
+                    .collect();
+                let _ = writeln!(md, "| {name} | {rows} | {} |", values.join(" | "));
+            }
+        }
+
+        md
+    }
+
+}
+
+
+fn optional(value: Option<f64>) -> String {
+    match value {
+        Some(value) => format!("{value:.4}"),
+        None => "-".to_string()
+    }
+}
@@ -0,0 +1,288 @@
+use std::path::Path;
+
+use ndarray::{concatenate, Array1, Array2, ArrayView1, Axis};
+use serde::{Serialize, Deserialize};
+
+use crate::Task;
+use crate::artifact::{read_json, write_json};
+use crate::config::ModelKind;
+use crate::error::*;
+use crate::preprocessing::Preprocessing;
+use crate::tabular::TabularModel;
+
+
+/// File a compact inference-only model is exported to
+pub const EXPORT_FILE: &str = "model.json";
+
+/// Directory exports are written to by default, one `<pipeline>/v<n>` directory per version
+pub const EXPORTS_DIR: &str = "exports";
+
+
+/// Trained linear model with only the state needed to predict.
+///
+/// Unlike dendritic's saved computation graph, which serializes a full
+/// training batch with every node, an export holds the learned weights and
+/// bias, the feature names and the fitted encoders. It can be loaded and
+/// used without dendritic rebuilding the graph.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportedModel {
+
+    /// Kind of model exported
+    pub model: ModelKind,
+
+    /// Prediction task of the model
+    pub task: Task,
+
+    /// Learned weights, one row per feature and one column per output
+    pub weights: Vec<Vec<f64>>,
+
+    /// Learned bias, one value per output
+    pub bias: Vec<f64>,
+
+    /// Feature names, target name and fitted encoders
+    pub preprocessing: Preprocessing
+}
+
+
+impl ExportedModel {
+
+    /// Export the parameters of a trained model.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - Trained model to take weights and bias from.
+    /// * `preprocessing` - Preprocessing the model was trained with.
+    /// * `task` - Prediction task of the model.
+    ///
+    pub fn from_model<M: TabularModel>(model: &M, preprocessing: &Preprocessing, task: Task) -> Self {
+        ExportedModel {
+            model: M::KIND,
+            task,
+            weights: model.weights().outer_iter().map(|row| row.to_vec()).collect(),
+            bias: model.bias().row(0).to_vec(),
+            preprocessing: preprocessing.clone()
+        }
+    }
+
+    /// Save export to a directory
+    pub fn save(&self, dir: &str) -> PipelineResult<()> {
+        write_json(dir, EXPORT_FILE, self)
+    }
+
+    /// Load export from a directory, checking its parameters line up
+    pub fn load(dir: &str) -> PipelineResult<Self> {
+
+        let path = Path::new(dir).join(EXPORT_FILE);
+        if !path.is_file() {
+            return Err(PipelineError::MissingFile { path: path.to_string_lossy().to_string() });
+        }
+
+        let exported: ExportedModel = read_json(dir, EXPORT_FILE)?;
+        let features = exported.preprocessing.features.len();
+        let outputs = exported.bias.len();
+        if exported.weights.len() != features || exported.weights.iter().any(|row| row.len() != outputs) {
+            return Err(PipelineError::ShapeMismatch {
+                context: format!("{dir} exported weights"),
+                expected: format!("{features} rows of {outputs} weights"),
+                found: format!("{} rows", exported.weights.len())
+            });
+        }
+        Ok(exported)
+    }
+
+    /// Feature names in the order the model expects them
+    pub fn features(&self) -> &[String] {
+        &self.preprocessing.features
+    }
+
+    /// Class names in code order, empty for regression models
+    pub fn classes(&self) -> Vec<String> {
+        let classes = match self.task {
+            Task::Regression => 0,
+            Task::Binary => 2,
+            Task::Multiclass => self.bias.len()
+        };
+        (0..classes).map(|class| self.preprocessing.class_name(class)).collect()
+    }
+
+    /// Linear output `xW + b` for encoded feature rows
+    pub fn decision_function(&self, encoded: &Array2<f64>) -> Array2<f64> {
+        let outputs = self.bias.len();
+        let weights = Array2::from_shape_fn((self.weights.len(), outputs), |(row, col)| self.weights[row][col]);
+        encoded.dot(&weights) + &Array1::from(self.bias.clone())
+    }
+
+    /// Predict on raw (untransformed) feature rows.
+    ///
+    /// Matches the saved model's predictions: regression outputs on the
+    /// original target scale, the positive class probability for binary
+    /// models and a `[label, probability]` row for multi class models.
+    pub fn predict(&self, x: &Array2<f64>) -> PipelineResult<Array2<f64>> {
+        match self.task {
+            Task::Regression => {
+                let encoded = self.preprocessing.x_encode.transform(&x.view())?;
+                let predictions = self.decision_function(&encoded);
+                match self.preprocessing.y_encode.is_scaler() {
+                    true => self.preprocessing.y_encode.inverse_transform(&predictions.view()),
+                    false => Ok(predictions)
+                }
+            },
+            Task::Binary => self.probabilities(x),
+            Task::Multiclass => {
+                let probabilities = self.probabilities(x)?;
+                let mut predictions = Array2::zeros((probabilities.nrows(), 2));
+                for (idx, row) in probabilities.axis_iter(Axis(0)).enumerate() {
+                    let label = argmax(&row);
+                    predictions[[idx, 0]] = label as f64;
+                    predictions[[idx, 1]] = row[label];
+                }
+                Ok(predictions)
+            }
+        }
+    }
+
+    /// Class probabilities for raw (untransformed) feature rows
+    pub fn probabilities(&self, x: &Array2<f64>) -> PipelineResult<Array2<f64>> {
+        if self.model != ModelKind::Logistic {
+            return Err(PipelineError::Config(format!(
+                "{:?} models do not predict class probabilities", self.model
+            )));
+        }
+        let encoded = self.preprocessing.x_encode.transform(&x.view())?;
+        Ok(class_probabilities(self.decision_function(&encoded)))
+    }
+
+    /// Predicted class code and the probability of every class for raw feature rows.
+    ///
+    /// Binary models get a probability column for the negative class before
+    /// the positive one, so columns line up with `classes`.
+    pub fn classify(&self, x: &Array2<f64>) -> PipelineResult<(Vec<usize>, Array2<f64>)> {
+        let mut probabilities = self.probabilities(x)?;
+        if self.task == Task::Binary {
+            probabilities = concatenate![Axis(1), probabilities.mapv(|p| 1.0 - p), probabilities];
+        }
+        let classes = probabilities.axis_iter(Axis(0)).map(|row| argmax(&row)).collect();
+        Ok((classes, probabilities))
+    }
+
+}
+
+
+/// Map logits to class probabilities.
+///
+/// A single column is passed through the sigmoid and gives the positive
+/// class probability, several columns are turned into a softmax per row.
+pub fn class_probabilities(mut logits: Array2<f64>) -> Array2<f64> {
+
+    if logits.ncols() == 1 {
+        logits.mapv_inplace(|z| 1.0 / (1.0 + (-z).exp()));
+        return logits;
+    }
+
+    for mut row in logits.axis_iter_mut(Axis(0)) {
+        let max = row.fold(f64::NEG_INFINITY, |acc, &z| acc.max(z));
+        row.mapv_inplace(|z| (z - max).exp());
+        let total = row.sum();
+        row.mapv_inplace(|p| p / total);
+    }
+    logits
+}
+
+
+/// Index of largest value in row, the first on ties
+pub fn argmax(row: &ArrayView1<f64>) -> usize {
+    row.iter()
+        .enumerate()
+        .fold((0, f64::NEG_INFINITY), |best, (idx, &v)| if v > best.1 { (idx, v) } else { best })
+        .0
+}
+
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use ndarray::array;
+
+    use super::*;
+    use crate::preprocessing::FittedEncoder;
+
+    /// Hand-built model over features `a` and `b` with unscaled inputs.
+    ///
+    /// Regression predicts `2a - b + 3`, the binary model has logit `a - b`
+    /// with classes `no` and `yes`, and the multi class model scores
+    /// `low`, `mid` and `high` with logits `-a`, `b` and `a`.
+    pub(crate) fn exported(task: Task) -> ExportedModel {
+        let (model, weights, bias, class_names) = match task {
+            Task::Regression => (ModelKind::Sgd, vec![vec![2.0], vec![-1.0]], vec![3.0], vec![]),
+            Task::Binary => (ModelKind::Logistic, vec![vec![1.0], vec![-1.0]], vec![0.0], vec!["no", "yes"]),
+            Task::Multiclass => (
+                ModelKind::Logistic,
+                vec![vec![-1.0, 0.0, 1.0], vec![0.0, 1.0, 0.0]],
+                vec![0.0; 3],
+                vec!["low", "mid", "high"]
+            )
+        };
+        ExportedModel {
+            model,
+            task,
+            weights,
+            bias,
+            preprocessing: Preprocessing {
+                features: vec!["a".to_string(), "b".to_string()],
+                target: "y".to_string(),
+                x_encode: FittedEncoder::None,
+                y_encode: FittedEncoder::None,
+                feature_stdev: vec![],
+                class_names: class_names.into_iter().map(String::from).collect()
+            }
+        }
+    }
+
+    #[test]
+    fn predicts_regression_and_classes() {
+        let x = array![[1.0, 2.0], [4.0, 1.0]];
+
+        let predictions = exported(Task::Regression).predict(&x).unwrap();
+        assert_eq!(predictions.column(0).to_vec(), [3.0, 10.0]);
+
+        let binary = exported(Task::Binary);
+        let (classes, probabilities) = binary.classify(&x).unwrap();
+        assert_eq!(classes, [0, 1]);
+        assert_eq!(binary.classes(), ["no", "yes"]);
+        let positive = 1.0 / (1.0 + (-3.0f64).exp());
+        assert!((probabilities[[1, 1]] - positive).abs() < 1e-12);
+        assert!((probabilities[[1, 0]] - (1.0 - positive)).abs() < 1e-12);
+
+        let multiclass = exported(Task::Multiclass);
+        let (classes, probabilities) = multiclass.classify(&x).unwrap();
+        assert_eq!(classes, [1, 2]);
+        assert_eq!(multiclass.predict(&x).unwrap().column(0).to_vec(), [1.0, 2.0]);
+        assert!(probabilities.rows().into_iter().all(|row| (row.sum() - 1.0).abs() < 1e-12));
+    }
+
+    #[test]
+    fn regression_models_have_no_probabilities() {
+        let x = array![[1.0, 2.0]];
+        let err = exported(Task::Regression).probabilities(&x).unwrap_err();
+        assert_eq!(err.kind(), "config");
+        assert!(exported(Task::Regression).classes().is_empty());
+    }
+
+    #[test]
+    fn load_checks_weight_shapes() {
+        let dir = std::env::temp_dir().join("crate_export_tests/mismatched");
+        let dir = dir.to_string_lossy();
+        let _ = std::fs::remove_dir_all(&*dir);
+
+        let mut model = exported(Task::Multiclass);
+        model.save(&dir).unwrap();
+        assert_eq!(ExportedModel::load(&dir).unwrap(), model);
+
+        model.weights.pop();
+        model.save(&dir).unwrap();
+        assert_eq!(ExportedModel::load(&dir).unwrap_err().kind(), "shape_mismatch");
+
+        let missing = std::env::temp_dir().join("crate_export_tests/missing");
+        assert_eq!(ExportedModel::load(&missing.to_string_lossy()).unwrap_err().kind(), "missing_file");
+    }
+
+}
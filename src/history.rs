@@ -0,0 +1,154 @@
+use std::fmt::Write;
+use std::fs;
+
+use polars::prelude::*;
+use serde::{Serialize, Deserialize};
+
+use crate::dataset::write_parquet;
+use crate::error::*;
+
+
+/// File the loss history is saved to as CSV
+pub const HISTORY_CSV_FILE: &str = "history.csv";
+
+/// File the loss history is saved to as parquet
+pub const HISTORY_PARQUET_FILE: &str = "history.parquet";
+
+
+/// Losses measured at the end of one training epoch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EpochRecord {
+
+    /// Epoch number counted over all iterations, starting at 1
+    pub epoch: usize,
+
+    /// Iteration the epoch belongs to, starting at 1
+    pub iteration: usize,
+
+    /// Mean loss of the epoch's mini-batches
+    pub train_loss: f64,
+
+    /// Loss on the validation partition, none without validation rows
+    pub validation_loss: Option<f64>,
+
+    /// Seconds since training started
+    pub elapsed_seconds: f64
+}
+
+
+/// Per epoch losses of a training run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrainingHistory {
+
+    /// Record of every epoch trained
+    pub epochs: Vec<EpochRecord>
+}
+
+
+impl TrainingHistory {
+
+    /// Add the record of an epoch
+    pub fn record(&mut self, record: EpochRecord) {
+        self.epochs.push(record);
+    }
+
+    /// Render history as CSV with a header row
+    pub fn to_csv(&self) -> String {
+
+        let mut csv = String::from("epoch,iteration,train_loss,validation_loss,elapsed_seconds\n");
+        for record in &self.epochs {
+            let validation_loss = record.validation_loss
+                .map(|loss| loss.to_string())
+                .unwrap_or_default();
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{}",
+                record.epoch, record.iteration, record.train_loss, validation_loss, record.elapsed_seconds
+            );
+        }
+        csv
+    }
+
+    /// History as a data frame with one row per epoch
+    pub fn to_frame(&self) -> PolarsResult<DataFrame> {
+        df!(
+            "epoch" => self.epochs.iter().map(|record| record.epoch as u64).collect::<Vec<_>>(),
+            "iteration" => self.epochs.iter().map(|record| record.iteration as u64).collect::<Vec<_>>(),
+            "train_loss" => self.epochs.iter().map(|record| record.train_loss).collect::<Vec<_>>(),
+            "validation_loss" => self.epochs.iter().map(|record| record.validation_loss).collect::<Vec<_>>(),
+            "elapsed_seconds" => self.epochs.iter().map(|record| record.elapsed_seconds).collect::<Vec<_>>()
+        )
+    }
+
+    /// Save history as CSV and parquet to model artifact directory
+    pub fn save(&self, dir: &str) -> PipelineResult<()> {
+
+        let path = format!("{dir}/{HISTORY_CSV_FILE}");
+        fs::write(&path, self.to_csv()).map_err(|err| PipelineError::artifact(&path, err))?;
+
+        let path = format!("{dir}/{HISTORY_PARQUET_FILE}");
+        let mut df = self.to_frame().map_err(|err| PipelineError::artifact(&path, err))?;
+        write_parquet(&path, &mut df)
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::dataset::read_table;
+
+    /// Four epochs over two iterations, validated on every other epoch
+    fn history() -> TrainingHistory {
+        let mut history = TrainingHistory::default();
+        for epoch in 1..=4 {
+            history.record(EpochRecord {
+                epoch,
+                iteration: epoch.div_ceil(2),
+                train_loss: 1.0 / epoch as f64,
+                validation_loss: (epoch % 2 == 0).then(|| 2.0 / epoch as f64),
+                elapsed_seconds: epoch as f64 * 0.25
+            });
+        }
+        history
+    }
+
+    /// Values of a float column, none where the column is null
+    fn floats(df: &DataFrame, column: &str) -> Vec<Option<f64>> {
+        df.column(column).unwrap().cast(&DataType::Float64).unwrap().f64().unwrap().into_iter().collect()
+    }
+
+    #[test]
+    fn renders_blank_validation_loss_in_csv() {
+        let csv = history().to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "epoch,iteration,train_loss,validation_loss,elapsed_seconds");
+        assert_eq!(lines[1], "1,1,1,,0.25");
+        assert_eq!(lines[2], "2,1,0.5,1,0.5");
+        assert_eq!(lines.len(), 5);
+    }
+
+    #[test]
+    fn saves_csv_and_parquet() {
+        let dir = std::env::temp_dir().join("crate_history_tests/saved");
+        let dir = dir.to_string_lossy();
+        let _ = fs::remove_dir_all(&*dir);
+        fs::create_dir_all(&*dir).unwrap();
+
+        let history = history();
+        history.save(&dir).unwrap();
+
+        for file in [HISTORY_CSV_FILE, HISTORY_PARQUET_FILE] {
+            let df = read_table(&format!("{dir}/{file}")).unwrap();
+            assert_eq!(df.height(), 4, "{file}");
+            assert_eq!(floats(&df, "epoch"), [Some(1.0), Some(2.0), Some(3.0), Some(4.0)], "{file}");
+            assert_eq!(floats(&df, "iteration"), [Some(1.0), Some(1.0), Some(2.0), Some(2.0)], "{file}");
+            assert_eq!(floats(&df, "train_loss"), history.epochs.iter().map(|record| Some(record.train_loss)).collect::<Vec<_>>(), "{file}");
+            assert_eq!(floats(&df, "validation_loss"), [None, Some(1.0), None, Some(0.5)], "{file}");
+            assert_eq!(floats(&df, "elapsed_seconds"), [Some(0.25), Some(0.5), Some(0.75), Some(1.0)], "{file}");
+        }
+    }
+
+}
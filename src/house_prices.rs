@@ -0,0 +1,57 @@
+use dendritic::optimizer::prelude::*;
+
+use crate::config::*;
+use crate::store::VersionSpec;
+use crate::tabular::TabularPipeline;
+
+
+// Testing & benchmarking model for house price regression with an optimizer
+pub type HousePrices = TabularPipeline<SGD>;
+
+
+/// Experiment config for California median house value regression
+pub fn config() -> ExperimentConfig {
+    ExperimentConfig {
+        name: "house_prices".to_string(),
+        output_dir: "models/house_prices".to_string(),
+        model_version: VersionSpec::Latest,
+        dataset: DatasetConfig {
+            path: "data/california_housing.parquet".to_string(),
+            test_path: None,
+            features: vec![
+                "longitude".to_string(),
+                "latitude".to_string(),
+                "housing_median_age".to_string(),
+                "total_rooms".to_string(),
+                "population".to_string(),
+                "median_income".to_string()
+            ],
+            target: "median_house_value".to_string(),
+            test_size: 0.2,
+            validation_size: 0.0,
+            split: SplitKind::Random,
+            group: None,
+            season: None,
+            labels: None
+        },
+        transforms: TransformConfig {
+            features: EncoderKind::MinMax,
+            target: EncoderKind::MinMax
+        },
+        model: ModelConfig {
+            kind: ModelKind::Sgd,
+            multi_class: false,
+            learning_rate: 0.01,
+            optimizer: OptimizerKind::Adam
+        },
+        hyperparameters: HyperparameterConfig {
+            iterations: 10,
+            batch_size: 256,
+            batch_epochs: 100
+        },
+        seed: DEFAULT_SEED,
+        cross_validation: CrossValidationConfig::default(),
+        search: SearchConfig::default(),
+        early_stopping: None
+    }
+}
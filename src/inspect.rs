@@ -0,0 +1,358 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::Path;
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value;
+
+use crate::Task;
+use crate::artifact::{read_json, RunMetadata, METADATA_FILE};
+use crate::config::{ExperimentConfig, ModelKind};
+use crate::error::*;
+use crate::export::{ExportedModel, EXPORT_FILE};
+use crate::registry::ModelStage;
+use crate::store::{ArtifactStore, VersionSpec, PARAMETERS_FILE};
+use crate::tabular;
+
+
+/// Learned weights of one feature and how much it moves the model's output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Coefficient {
+
+    /// Column the weights apply to
+    pub feature: String,
+
+    /// Learned weight for every output, on the encoded feature scale
+    pub weights: Vec<f64>,
+
+    /// Change in every output for a one standard deviation change of the raw feature
+    pub standardized: Vec<f64>,
+
+    /// Mean absolute standardized weight across outputs
+    pub importance: f64,
+
+    /// Position when features are ordered by importance, 1 is the most important
+    pub rank: usize
+}
+
+
+/// Parameters of a saved model mapped to the feature names it was trained on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Inspection {
+
+    /// Name of experiment the model belongs to
+    pub pipeline: String,
+
+    /// Version number inspected
+    pub version: u32,
+
+    /// Registry stage of the version
+    pub stage: ModelStage,
+
+    /// Kind of model saved
+    pub model: ModelKind,
+
+    /// Prediction task of the model
+    pub task: Task,
+
+    /// Target column the model predicts
+    pub target: String,
+
+    /// Class labels in code order, empty for regression
+    #[serde(default)]
+    pub classes: Vec<String>,
+
+    /// Weights of every feature in the order the model expects them
+    pub coefficients: Vec<Coefficient>,
+
+    /// Learned bias, one value per output
+    pub bias: Vec<f64>,
+
+    /// Settings saved in parameters.json other than the graph path
+    pub parameters: BTreeMap<String, Value>,
+
+    /// Settings the model was trained with, if metadata was saved
+    pub training: Option<RunMetadata>,
+
+    /// Caveats on how the importance was computed
+    pub notes: Vec<String>
+}
+
+
+impl Inspection {
+
+    /// Inspect the saved version an experiment config names
+    pub fn load(config: &ExperimentConfig) -> PipelineResult<Self> {
+
+        let store = ArtifactStore::new(&config.output_dir);
+        let manifest = store.resolve(config.model_version)?;
+        let dir = store.version_dir(manifest.version);
+
+        let exported = match Path::new(&dir).join(EXPORT_FILE).is_file() {
+            true => ExportedModel::load(&dir)?,
+            false => tabular::export_version(&dir, config.model.kind, config.task())?
+        };
+
+        let parameters: Value = read_json(&dir, PARAMETERS_FILE)?;
+        let training = match Path::new(&dir).join(METADATA_FILE).is_file() {
+            true => Some(RunMetadata::load(&dir)?),
+            false => None
+        };
+
+        let mut inspection = Inspection::from_export(&config.name, manifest.version, &exported);
+        inspection.stage = store.registry()?.stage(manifest.version);
+        inspection.parameters = flatten(&parameters);
+        inspection.training = training;
+        Ok(inspection)
+    }
+
+    /// Map the weights of an exported model to its feature names and rank them.
+    ///
+    /// Weights apply to encoded features, so they are divided by the scale
+    /// the feature encoder applied and multiplied by the feature's training
+    /// standard deviation to compare features measured in different units.
+    /// Exports without recorded deviations are ranked on their encoded
+    /// weights.
+    ///
+    /// # Arguments
+    ///
+    /// * `pipeline` - Name of experiment the model belongs to.
+    /// * `version` - Version number of the export.
+    /// * `exported` - Exported model to inspect.
+    ///
+    pub fn from_export(pipeline: &str, version: u32, exported: &ExportedModel) -> Self {
+
+        let preprocessing = &exported.preprocessing;
+        let features = preprocessing.features.len();
+        let scales = preprocessing.x_encode.scales(features);
+
+        let mut notes = Vec::new();
+        let spread = match preprocessing.feature_stdev.len() == features {
+            true => preprocessing.feature_stdev.clone(),
+            false => {
+                notes.push("feature deviations were not recorded with this version, importance uses encoded weights (retrain to record them)".to_string());
+                scales.clone()
+            }
+        };
+
+        let mut coefficients: Vec<Coefficient> = preprocessing.features
+            .iter()
+            .enumerate()
+            .map(|(idx, feature)| {
+                let weights = exported.weights[idx].clone();
+                let standardized: Vec<f64> = weights.iter().map(|weight| weight / scales[idx] * spread[idx]).collect();
+                let importance = standardized.iter().map(|weight| weight.abs()).sum::<f64>() / standardized.len().max(1) as f64;
+                Coefficient { feature: feature.clone(), weights, standardized, importance, rank: 0 }
+            })
+            .collect();
+
+        let mut order: Vec<usize> = (0..coefficients.len()).collect();
+        order.sort_by(|&a, &b| coefficients[b].importance.total_cmp(&coefficients[a].importance));
+        for (rank, idx) in order.into_iter().enumerate() {
+            coefficients[idx].rank = rank + 1;
+        }
+
+        Inspection {
+            pipeline: pipeline.to_string(),
+            version,
+            stage: ModelStage::default(),
+            model: exported.model,
+            task: exported.task,
+            target: preprocessing.target.clone(),
+            classes: exported.classes(),
+            coefficients,
+            bias: exported.bias.clone(),
+            parameters: BTreeMap::new(),
+            training: None,
+            notes
+        }
+    }
+
+    /// Names of the model's outputs, one per weight column
+    fn outputs(&self) -> Vec<String> {
+        match self.task {
+            Task::Multiclass => (0..self.bias.len())
+                .map(|class| self.classes.get(class).cloned().unwrap_or(format!("class {class}")))
+                .collect(),
+            _ => vec![self.target.clone()]
+        }
+    }
+
+    /// Render inspection as Markdown
+    pub fn to_markdown(&self) -> String {
+
+        let outputs = self.outputs();
+        let mut md = String::new();
+        let _ = writeln!(md, "# {} {}\n", self.pipeline, VersionSpec::Pinned(self.version));
+        let _ = writeln!(
+            md,
+            "{} | {:?} | {} | target {}",
+            self.stage,
+            self.model,
+            self.task.name(),
+            self.target
+        );
+
+        let _ = writeln!(md, "\n## Coefficients\n");
+        let weight_headers: Vec<String> = outputs.iter().map(|output| format!("{output} weight")).collect();
+        let standard_headers: Vec<String> = outputs.iter().map(|output| format!("{output} standardized")).collect();
+        let _ = writeln!(
+            md,
+            "| Rank | Feature | {} | {} | Importance |",
+            weight_headers.join(" | "),
+            standard_headers.join(" | ")
+        );
+        let _ = writeln!(md, "| --- | --- |{} --- |", " --- |".repeat(outputs.len() * 2));
+
+        let mut ranked: Vec<&Coefficient> = self.coefficients.iter().collect();
+        ranked.sort_by_key(|coefficient| coefficient.rank);
+        for coefficient in ranked {
+            let _ = writeln!(
+                md,
+                "| {} | {} | {} | {} | {:.4} |",
+                coefficient.rank,
+                coefficient.feature,
+                join(&coefficient.weights),
+                join(&coefficient.standardized),
+                coefficient.importance
+            );
+        }
+
+        let _ = writeln!(md, "\n## Bias\n");
+        let _ = writeln!(md, "| Output | Bias |");
+        let _ = writeln!(md, "| --- | --- |");
+        for (output, bias) in outputs.iter().zip(&self.bias) {
+            let _ = writeln!(md, "| {output} | {bias:.6} |");
+        }
+
+        let _ = writeln!(md, "\n## Hyperparameters\n");
+        let _ = writeln!(md, "| Parameter | Value |");
+        let _ = writeln!(md, "| --- | --- |");
+        for (name, value) in &self.parameters {
+            let _ = writeln!(md, "| {name} | {value} |");
+        }
+
+        if let Some(training) = &self.training {
+            let hyperparameters = &training.hyperparameters;
+            let _ = writeln!(md, "\n## Training\n");
+            let _ = writeln!(md, "| Setting | Value |");
+            let _ = writeln!(md, "| --- | --- |");
+            for (name, value) in [
+                ("optimizer", format!("{:?}", training.model.optimizer).to_lowercase()),
+                ("iterations", hyperparameters.iterations.to_string()),
+                ("batch_size", hyperparameters.batch_size.to_string()),
+                ("batch_epochs", hyperparameters.batch_epochs.to_string()),
+                ("seed", training.seed.to_string()),
+                ("train_rows", training.train_rows.to_string())
+            ] {
+                let _ = writeln!(md, "| {name} | {value} |");
+            }
+        }
+
+        if !self.notes.is_empty() {
+            let _ = writeln!(md, "\n## Notes\n");
+            for note in &self.notes {
+                let _ = writeln!(md, "- {note}");
+            }
+        }
+
+        md
+    }
+
+}
+
+
+/// Parameters of a parameters.json file keyed by their dotted path, without the graph path
+fn flatten(value: &Value) -> BTreeMap<String, Value> {
+
+    fn walk(prefix: &str, value: &Value, flat: &mut BTreeMap<String, Value>) {
+        match value {
+            Value::Object(fields) => {
+                for (name, field) in fields {
+                    let path = match prefix.is_empty() {
+                        true => name.clone(),
+                        false => format!("{prefix}.{name}")
+                    };
+                    walk(&path, field, flat);
+                }
+            },
+            _ if prefix.ends_with("graph_path") => {},
+            _ => {
+                flat.insert(prefix.to_string(), value.clone());
+            }
+        }
+    }
+
+    let mut flat = BTreeMap::new();
+    walk("", value, &mut flat);
+    flat
+}
+
+
+fn join(values: &[f64]) -> String {
+    values
+        .iter()
+        .map(|value| format!("{value:.4}"))
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::export::tests::exported;
+    use crate::preprocessing::FittedEncoder;
+
+    #[test]
+    fn standardizes_weights_by_encoder_scale_and_feature_spread() {
+        let mut model = exported(Task::Regression);
+        model.preprocessing.x_encode = FittedEncoder::Standard { mean: vec![0.0, 5.0], stdev: vec![4.0, 0.5] };
+        model.preprocessing.feature_stdev = vec![2.0, 10.0];
+
+        let inspection = Inspection::from_export("prices", 3, &model);
+        let [a, b] = &inspection.coefficients[..] else { panic!("expected two coefficients") };
+
+        // a: 2 / 4 * 2 = 1, b: -1 / 0.5 * 10 = -20, so b outranks a despite its smaller weight
+        assert_eq!((a.feature.as_str(), &a.weights, &a.standardized, a.importance, a.rank), ("a", &vec![2.0], &vec![1.0], 1.0, 2));
+        assert_eq!((b.feature.as_str(), &b.weights, &b.standardized, b.importance, b.rank), ("b", &vec![-1.0], &vec![-20.0], 20.0, 1));
+        assert!(inspection.notes.is_empty());
+
+        let md = inspection.to_markdown();
+        assert!(md.contains("# prices v3"), "{md}");
+        assert!(md.find("| 1 | b | -1.0000 | -20.0000 | 20.0000 |").unwrap() < md.find("| 2 | a | 2.0000 | 1.0000 | 1.0000 |").unwrap(), "{md}");
+        assert!(md.contains("| y | 3.000000 |") && !md.contains("## Notes"), "{md}");
+    }
+
+    #[test]
+    fn averages_importance_over_classes_by_name() {
+        let mut model = exported(Task::Multiclass);
+        model.preprocessing.feature_stdev = vec![1.0, 3.0];
+
+        let inspection = Inspection::from_export("tiers", 1, &model);
+        assert_eq!(inspection.classes, ["low", "mid", "high"]);
+        assert_eq!(inspection.coefficients[0].importance, 2.0 / 3.0);
+        assert_eq!(inspection.coefficients[1].importance, 1.0);
+        assert_eq!(inspection.coefficients.iter().map(|coefficient| coefficient.rank).collect::<Vec<_>>(), [2, 1]);
+
+        let md = inspection.to_markdown();
+        assert!(md.contains("| Rank | Feature | low weight | mid weight | high weight | low standardized | mid standardized | high standardized | Importance |"), "{md}");
+        assert!(md.contains("| mid | 0.000000 |") && !md.contains("class 1"), "{md}");
+    }
+
+    #[test]
+    fn notes_exports_without_feature_deviations() {
+        let inspection = Inspection::from_export("churn", 2, &exported(Task::Binary));
+        assert_eq!(inspection.notes.len(), 1);
+        assert!(inspection.notes[0].contains("feature deviations were not recorded"));
+
+        // Encoded weights are ranked as they are
+        let standardized: Vec<&Vec<f64>> = inspection.coefficients.iter().map(|coefficient| &coefficient.standardized).collect();
+        assert_eq!(standardized, [&vec![1.0], &vec![-1.0]]);
+        let md = inspection.to_markdown();
+        assert!(md.contains("## Notes\n\n- feature deviations were not recorded"), "{md}");
+        assert!(md.contains("| Rank | Feature | y weight | y standardized | Importance |"), "{md}");
+    }
+
+}
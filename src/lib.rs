@@ -1,6 +1,175 @@
 pub mod iris;
 pub mod breast_cancer;
-pub mod dowj_stocks;
 pub mod student_performance;
 pub mod coca_cola_stock;
-pub mod titanic; 
+pub mod titanic;
+pub mod house_prices;
+pub mod monero;
+pub mod pipeline;
+pub mod config;
+pub mod tabular;
+pub mod error;
+pub mod dataset;
+pub mod preprocessing;
+pub mod split;
+pub mod artifact;
+pub mod metrics;
+pub mod evaluation;
+pub mod cross_validation;
+pub mod search;
+pub mod history;
+pub mod baseline;
+pub mod benchmark;
+pub mod store;
+pub mod registry;
+pub mod export;
+pub mod onnx;
+pub mod schema;
+pub mod inspect;
+pub mod serve;
+pub mod batch;
+
+use serde::{Serialize, Deserialize};
+use dendritic::optimizer::prelude::SGD;
+use dendritic::optimizer::regression::logistic::Logistic;
+
+use crate::config::ExperimentConfig;
+use crate::error::PipelineResult;
+use crate::pipeline::Pipeline;
+use crate::tabular::{TabularModel, TabularPipeline};
+
+
+/// Kind of prediction problem a pipeline solves
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Task {
+    Regression,
+    Binary,
+    Multiclass
+}
+
+
+impl Task {
+
+    /// Name of task used in listings and reports
+    pub fn name(&self) -> &'static str {
+        match self {
+            Task::Regression => "regression",
+            Task::Binary => "binary",
+            Task::Multiclass => "multiclass"
+        }
+    }
+
+}
+
+
+/// Registry entry describing a single model pipeline
+pub struct PipelineEntry {
+
+    /// Stable name used to target the pipeline
+    pub name: &'static str,
+
+    /// Name of type implementing the pipeline
+    pub model: &'static str,
+
+    /// Built in experiment config of the pipeline
+    config: fn() -> ExperimentConfig,
+
+    /// Constructor building the pipeline from its config
+    constructor: fn(ExperimentConfig) -> PipelineResult<Box<dyn Pipeline>>
+}
+
+
+impl PipelineEntry {
+
+    /// Experiment config the pipeline is built from
+    pub fn config(&self) -> ExperimentConfig {
+        (self.config)()
+    }
+
+    /// Dataset files read by the pipeline
+    pub fn datasets(&self) -> Vec<String> {
+        let dataset = self.config().dataset;
+        std::iter::once(dataset.path).chain(dataset.test_path).collect()
+    }
+
+    /// Prediction task of the pipeline
+    pub fn task(&self) -> Task {
+        self.config().task()
+    }
+
+    /// Directory model artifacts are saved to
+    pub fn artifact_dir(&self) -> String {
+        self.config().output_dir
+    }
+
+    /// Build a new instance of the pipeline
+    pub fn build(&self) -> PipelineResult<Box<dyn Pipeline>> {
+        (self.constructor)(self.config())
+    }
+
+    /// Build a new instance of the pipeline from a modified config,
+    /// which must keep the model type of the entry
+    pub fn build_from(&self, config: ExperimentConfig) -> PipelineResult<Box<dyn Pipeline>> {
+        (self.constructor)(config)
+    }
+
+}
+
+
+fn construct<M: TabularModel + 'static>(config: ExperimentConfig) -> PipelineResult<Box<dyn Pipeline>> {
+    Ok(Box::new(TabularPipeline::<M>::from_config(config)?))
+}
+
+
+/// Every model pipeline in the crate
+pub const REGISTRY: &[PipelineEntry] = &[
+    PipelineEntry {
+        name: "iris",
+        model: "IrisFlowersModel",
+        config: iris::config,
+        constructor: construct::<Logistic>
+    },
+    PipelineEntry {
+        name: "breast_cancer",
+        model: "BreastCancerModel",
+        config: breast_cancer::config,
+        constructor: construct::<Logistic>
+    },
+    PipelineEntry {
+        name: "student_performance",
+        model: "StudentPerformance",
+        config: student_performance::config,
+        constructor: construct::<SGD>
+    },
+    PipelineEntry {
+        name: "coca_cola_stock",
+        model: "CocaColaStockModel",
+        config: coca_cola_stock::config,
+        constructor: construct::<SGD>
+    },
+    PipelineEntry {
+        name: "titanic",
+        model: "TitanicModel",
+        config: titanic::config,
+        constructor: construct::<Logistic>
+    },
+    PipelineEntry {
+        name: "house_prices",
+        model: "HousePrices",
+        config: house_prices::config,
+        constructor: construct::<SGD>
+    },
+    PipelineEntry {
+        name: "monero",
+        model: "MoneroModel",
+        config: monero::config,
+        constructor: construct::<SGD>
+    },
+];
+
+
+/// Look up pipeline entry by its registry name
+pub fn lookup(name: &str) -> Option<&'static PipelineEntry> {
+    REGISTRY.iter().find(|entry| entry.name == name)
+}
@@ -1,13 +1,676 @@
-use dendritic::optimizer::model::*; 
-use dendritic_ml_models::coca_cola_stock::CocaColaStockModel;
-use dendritic_ml_models::breast_cancer::BreastCancerModel;
-use dendritic_ml_models::iris::*;
-use dendritic_ml_models::titanic::*;
+use std::fs;
+use std::net::TcpListener;
+use std::path::Path;
+use std::process;
+
+use dendritic_ml_models::{lookup, REGISTRY};
+use dendritic_ml_models::pipeline::*;
+use dendritic_ml_models::benchmark::{BenchmarkResult, Leaderboard, BENCHMARK_DIR};
+use dendritic_ml_models::store::{ArtifactStore, VersionSpec};
+use dendritic_ml_models::registry::ModelStage;
+use dendritic_ml_models::export::EXPORT_FILE;
+use dendritic_ml_models::onnx::ONNX_FILE;
+use dendritic_ml_models::schema;
+use dendritic_ml_models::inspect::Inspection;
+use dendritic_ml_models::batch::predict_file;
+use dendritic_ml_models::serve::{ModelServer, ServedModel, DEFAULT_PORT};
+use dendritic_ml_models::config::ExperimentConfig;
+use dendritic_ml_models::tabular;
+use dendritic_ml_models::error::*;
+
+
+const USAGE: &str = "\
+Usage: dendritic_ml_models <command> [pipeline...] [options]
+
+Pipelines are registry names, experiment names in experiments/ or paths to
+experiment config files (*.toml).
+
+Commands:
+  list                 List available pipelines and experiments
+  load <pipeline>      Run the load stage
+  train <pipeline>     Run the load, transform and train stages
+  infer <pipeline>     Run the load and inference stages with the saved model
+  evaluate <pipeline>  Score the saved model on held out rows, writing
+                       evaluation.json and evaluation.md next to it
+  run-all              Run every stage for every pipeline
+  cross-validate <pipeline>
+                       Run k-fold cross validation on the non test rows,
+                       writing cross_validation.json and cross_validation.md
+  search <pipeline>    Search hyperparameters from the config's search space,
+                       writing search.json and search.md and saving the best model
+  versions <pipeline>  List saved model versions, their stages and promotions
+  promote <pipeline>   Move the model version given by --version (latest by
+                       default) to the registry stage given by --to
+  prune <pipeline>     Remove stale graph files that shadow saved models
+  validate <pipeline>  Check the schema, checksums, parameters and graph of every saved
+                       version (or the one given by --version)
+  migrate <pipeline>   Upgrade saved versions to the current artifact schema and import
+                       a legacy flat artifact directory (--from, or the store root)
+  export <pipeline>    Write the weights, bias, feature names and preprocessing of
+                       the saved model to a compact model.json without the graph,
+                       and the model with its preprocessing as an ONNX model.onnx
+  predict <pipeline>   Score every row of the parquet or CSV file given by --input with
+                       the saved model, writing the rows with a row_id, predictions and
+                       class probabilities to --output (default <input>_predictions)
+  inspect <pipeline>   Print the saved model's coefficients by feature name, its bias,
+                       a standardized importance ranking and its hyperparameters
+  serve [pipeline...]  Serve the saved models of the pipelines given (or every registered
+                       pipeline with a saved version) as JSON over HTTP on localhost:
+                       GET /health, GET /v1/models, POST /v1/models/<name>/predict
+  benchmark [pipeline...]
+                       Train and evaluate every registered pipeline (or those given)
+                       and write leaderboard.json and leaderboard.md
+
+Options:
+  --stages <stage,...>  Override stages to run (load, transform, train, evaluate, inference)
+  --seed <n>            Override run seed driving splits and mini-batch order
+  --version <v>         Saved model version to evaluate, infer with or inspect (latest, v<n>
+                        or a stage such as production)
+  --to <stage>          Stage to promote to (staging, production or archived)
+  --reason <text>       Why the version is promoted, recorded in the registry
+  --by <name>           Who promotes the version (default $USER)
+  --from <dir>          Legacy artifact directory to import with migrate
+  --port <n>            Port serve listens on (default 8080)
+  --folds <n>           Override number of cross validation folds
+  --repeats <n>         Override number of cross validation repeats
+  --input <file>        Parquet or CSV file predict scores
+  --output <dir>        Directory benchmark runs and leaderboard are written to (default
+                        benchmarks), or an export is written to (default exports/<pipeline>/v<n>),
+                        or file predict writes";
+
+
+/// Parsed command line arguments
+struct Args {
+
+    /// Subcommand to run
+    command: String,
+
+    /// Pipelines targeted by the command
+    pipelines: Vec<String>,
+
+    /// Stages explicitly requested with `--stages`
+    stages: Option<Vec<Stage>>,
+
+    /// Run seed overriding the one in each config
+    seed: Option<u64>,
+
+    /// Cross validation folds overriding the config
+    folds: Option<usize>,
+
+    /// Cross validation repeats overriding the config
+    repeats: Option<usize>,
+
+    /// Directory benchmark or export output is written to, or file predictions are written to
+    output: Option<String>,
+
+    /// File of rows to score
+    input: Option<String>,
+
+    /// Saved model version overriding the config
+    version: Option<VersionSpec>,
+
+    /// Registry stage a version is promoted to
+    to: Option<ModelStage>,
+
+    /// Reason recorded with a promotion
+    reason: Option<String>,
+
+    /// Who a promotion is recorded as made by
+    by: Option<String>,
+
+    /// Legacy artifact directory to migrate
+    from: Option<String>,
+
+    /// Port the prediction server listens on
+    port: Option<u16>
+}
+
+
+fn parse_args(args: &[String]) -> Result<Args, String> {
+
+    let command = args.first().ok_or("Missing command")?.clone();
+    let mut pipelines = Vec::new();
+    let mut stages = None;
+    let mut seed = None;
+    let mut folds = None;
+    let mut repeats = None;
+    let mut output = None;
+    let mut input = None;
+    let mut version = None;
+    let mut to = None;
+    let mut reason = None;
+    let mut by = None;
+    let mut from = None;
+    let mut port = None;
+
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        if arg == "--stages" {
+            let value = iter.next().ok_or("Missing value for --stages")?;
+            let parsed = value
+                .split(',')
+                .map(|s| Stage::parse(s.trim()).ok_or(format!("Unknown stage: {s}")))
+                .collect::<Result<Vec<_>, _>>()?;
+            stages = Some(parsed);
+        } else if arg == "--seed" {
+            let value = iter.next().ok_or("Missing value for --seed")?;
+            seed = Some(value.parse().map_err(|_| format!("Invalid seed: {value}"))?);
+        } else if arg == "--folds" {
+            let value = iter.next().ok_or("Missing value for --folds")?;
+            folds = Some(value.parse().map_err(|_| format!("Invalid folds: {value}"))?);
+        } else if arg == "--repeats" {
+            let value = iter.next().ok_or("Missing value for --repeats")?;
+            repeats = Some(value.parse().map_err(|_| format!("Invalid repeats: {value}"))?);
+        } else if arg == "--version" {
+            let value = iter.next().ok_or("Missing value for --version")?;
+            version = Some(VersionSpec::parse(value)?);
+        } else if arg == "--to" {
+            let value = iter.next().ok_or("Missing value for --to")?;
+            to = Some(ModelStage::parse(value).ok_or(format!("Unknown model stage: {value}"))?);
+        } else if arg == "--reason" {
+            reason = Some(iter.next().ok_or("Missing value for --reason")?.clone());
+        } else if arg == "--by" {
+            by = Some(iter.next().ok_or("Missing value for --by")?.clone());
+        } else if arg == "--from" {
+            from = Some(iter.next().ok_or("Missing value for --from")?.clone());
+        } else if arg == "--port" {
+            let value = iter.next().ok_or("Missing value for --port")?;
+            port = Some(value.parse().map_err(|_| format!("Invalid port: {value}"))?);
+        } else if arg == "--input" {
+            input = Some(iter.next().ok_or("Missing value for --input")?.clone());
+        } else if arg == "--output" {
+            output = Some(iter.next().ok_or("Missing value for --output")?.clone());
+        } else if arg.starts_with("--") {
+            return Err(format!("Unknown option: {arg}"));
+        } else {
+            pipelines.push(arg.clone());
+        }
+    }
+
+    Ok(Args { command, pipelines, stages, seed, folds, repeats, output, input, version, to, reason, by, from, port })
+}
+
+
+/// Work a command performs for each targeted pipeline
+enum Action {
+
+    /// Run pipeline stages in order
+    Stages(Vec<Stage>),
+
+    /// Cross validate the pipeline's model
+    CrossValidate,
+
+    /// Search hyperparameters and save the best model
+    Search,
+
+    /// List saved model versions
+    Versions,
+
+    /// Move a saved model version to another registry stage
+    Promote,
+
+    /// Remove stale graph files from saved models
+    Prune,
+
+    /// Write a compact inference-only export of a saved model
+    Export,
+
+    /// Check saved model versions against the artifact schema
+    Validate,
+
+    /// Upgrade saved model versions to the current artifact schema
+    Migrate,
+
+    /// Print the coefficients and hyperparameters of a saved model
+    Inspect,
+
+    /// Score a file of rows with a saved model
+    Predict
+}
+
+
+/// Step a pipeline failed in along with the error
+type Failure = (&'static str, PipelineError);
+
+
+fn resolve_config(name: &str) -> PipelineResult<ExperimentConfig> {
+
+    if let Some(entry) = lookup(name) {
+        return Ok(entry.config());
+    }
+
+    let path = if name.ends_with(".toml") {
+        name.to_string()
+    } else {
+        ExperimentConfig::path_for(name)
+    };
+
+    if Path::new(&path).exists() {
+        return ExperimentConfig::from_file(&path).map_err(PipelineError::Config);
+    }
+
+    Err(PipelineError::Config(format!("Unknown pipeline: {name}")))
+}
+
+
+/// Build registered pipeline or generic tabular pipeline from config
+fn build(name: &str, config: ExperimentConfig) -> PipelineResult<Box<dyn Pipeline>> {
+    match lookup(name) {
+        Some(entry) => entry.build_from(config),
+        None => tabular::build(config)
+    }
+}
+
+
+/// Run stages for a pipeline, reporting the stage that failed
+fn run(name: &str, stages: &[Stage], args: &Args) -> Result<(), Failure> {
+    let mut pipeline = build(name, configure(name, args)?).map_err(|err| ("setup", err))?;
+    for stage in stages {
+        pipeline.run_stage(*stage).map_err(|err| (stage.name(), err))?;
+    }
+    Ok(())
+}
+
+
+/// Resolve config with command line overrides applied
+fn configure(name: &str, args: &Args) -> Result<ExperimentConfig, Failure> {
+    let mut config = resolve_config(name).map_err(|err| ("setup", err))?.with_seed(args.seed);
+    config.cross_validation.folds = args.folds.unwrap_or(config.cross_validation.folds);
+    config.cross_validation.repeats = args.repeats.unwrap_or(config.cross_validation.repeats);
+    config.model_version = args.version.unwrap_or(config.model_version);
+    config.validate().map_err(|err| ("setup", PipelineError::Config(err)))?;
+    Ok(config)
+}
+
+
+/// Cross validate a pipeline and save the results next to its model
+fn cross_validate(name: &str, args: &Args) -> Result<(), Failure> {
+
+    let config = configure(name, args)?;
+    let output_dir = config.output_dir.clone();
+    let results = tabular::cross_validate(config).map_err(|err| ("cross-validate", err))?;
+    results.save(&output_dir).map_err(|err| ("cross-validate", err))?;
+    println!("{}", results.to_markdown());
+    Ok(())
+}
+
+
+/// Print the saved versions of a pipeline, newest last
+fn versions(name: &str, args: &Args) -> Result<(), Failure> {
+
+    let config = configure(name, args)?;
+    let store = ArtifactStore::new(&config.output_dir);
+    let manifests = store.versions().map_err(|err| ("versions", err))?;
+    if manifests.is_empty() {
+        println!("No saved versions in {}", store.root());
+        return Ok(());
+    }
+
+    let registry = store.registry().map_err(|err| ("versions", err))?;
+    println!("{:<10}{:<12}{:<24}{:<10}{:<8}files", "version", "stage", "created", "model", "seed");
+    for manifest in &manifests {
+        println!(
+            "{:<10}{:<12}{:<24}{:<10}{:<8}{}",
+            manifest.name(),
+            registry.stage(manifest.version).name(),
+            manifest.created_at,
+            format!("{:?}", manifest.model).to_lowercase(),
+            manifest.seed,
+            manifest.files.len()
+        );
+    }
+
+    if !registry.promotions.is_empty() {
+        println!("\nPromotions");
+        for promotion in &registry.promotions {
+            println!(
+                "{}  {} {} -> {} by {}: {}",
+                promotion.at,
+                VersionSpec::Pinned(promotion.version),
+                promotion.from,
+                promotion.to,
+                promotion.by,
+                promotion.reason
+            );
+        }
+    }
+    Ok(())
+}
+
+
+/// Move a saved version of a pipeline to the stage given with `--to`
+fn promote(name: &str, args: &Args) -> Result<(), Failure> {
+
+    let config = configure(name, args)?;
+    let to = args.to.ok_or(("promote", PipelineError::Config("promote requires --to <stage>".to_string())))?;
+    let reason = args.reason.as_deref().unwrap_or_default();
+    let by = match &args.by {
+        Some(by) => by.clone(),
+        None => std::env::var("USER").unwrap_or("unknown".to_string())
+    };
+
+    let store = ArtifactStore::new(&config.output_dir);
+    let recorded = store
+        .promote(config.model_version, to, &by, reason)
+        .map_err(|err| ("promote", err))?;
+    for promotion in &recorded {
+        println!(
+            "Moved {} {} from {} to {}",
+            config.name,
+            VersionSpec::Pinned(promotion.version),
+            promotion.from,
+            promotion.to
+        );
+    }
+    Ok(())
+}
+
+
+/// Remove stale graph files from every saved version of a pipeline
+fn prune(name: &str, args: &Args) -> Result<(), Failure> {
+    let config = configure(name, args)?;
+    let removed = ArtifactStore::new(&config.output_dir).prune().map_err(|err| ("prune", err))?;
+    for path in &removed {
+        println!("Removed {path}");
+    }
+    println!("Pruned {} stale graph files from {}", removed.len(), config.output_dir);
+    Ok(())
+}
+
+
+/// Export the saved model of a pipeline without its computation graph
+fn export(name: &str, args: &Args) -> Result<(), Failure> {
+    let config = configure(name, args)?;
+    let dir = tabular::export(&config, args.output.as_deref()).map_err(|err| ("export", err))?;
+    println!("Exported {} {} to {dir}/{EXPORT_FILE} and {dir}/{ONNX_FILE}", config.name, config.model_version);
+    Ok(())
+}
+
+
+/// Validate saved versions of a pipeline, failing if any has a blocking issue
+fn validate(name: &str, args: &Args) -> Result<(), Failure> {
+
+    let config = configure(name, args)?;
+    let store = ArtifactStore::new(&config.output_dir);
+    let dirs = match args.version {
+        Some(spec) => vec![store.dir(spec).map_err(|err| ("validate", err))?],
+        None => store
+            .versions()
+            .map_err(|err| ("validate", err))?
+            .iter()
+            .map(|manifest| store.version_dir(manifest.version))
+            .collect()
+    };
+    if dirs.is_empty() {
+        println!("No saved versions in {}", store.root());
+    }
+
+    let mut invalid = Vec::new();
+    for dir in dirs {
+        let report = schema::validate(&dir);
+        let status = if report.is_valid() { "ok" } else { "invalid" };
+        println!("{dir}: {status}");
+        for issue in &report.issues {
+            println!("  {issue}");
+        }
+        if !report.is_valid() {
+            invalid.push(dir);
+        }
+    }
+
+    match invalid.is_empty() {
+        true => Ok(()),
+        false => Err(("validate", PipelineError::InvalidArtifact {
+            path: invalid.join(", "),
+            message: "see issues above".to_string()
+        }))
+    }
+}
+
+
+/// Upgrade saved versions of a pipeline and import its legacy artifacts
+fn migrate(name: &str, args: &Args) -> Result<(), Failure> {
+    let config = configure(name, args)?;
+    let notes = schema::migrate(&config, args.from.as_deref()).map_err(|err| ("migrate", err))?;
+    if notes.is_empty() {
+        println!("{} is up to date", config.output_dir);
+    }
+    for note in &notes {
+        println!("{note}");
+    }
+    Ok(())
+}
+
+
+/// Score the file given with `--input` with the saved model of a pipeline
+fn predict(name: &str, args: &Args) -> Result<(), Failure> {
+    let config = configure(name, args)?;
+    let input = args.input.as_deref().ok_or(("predict", PipelineError::Config("predict requires --input <file>".to_string())))?;
+    let (version, exported) = tabular::load_export(&config).map_err(|err| ("predict", err))?;
+    let scored = predict_file(&exported, input, args.output.as_deref()).map_err(|err| ("predict", err))?;
+    println!(
+        "Scored {} rows of {input} with {} {} into {}",
+        scored.rows,
+        config.name,
+        VersionSpec::Pinned(version),
+        scored.output
+    );
+    Ok(())
+}
+
+
+/// Print the named coefficients, importance and hyperparameters of a saved model
+fn inspect(name: &str, args: &Args) -> Result<(), Failure> {
+    let config = configure(name, args)?;
+    let inspection = Inspection::load(&config).map_err(|err| ("inspect", err))?;
+    println!("{}", inspection.to_markdown());
+    Ok(())
+}
+
+
+/// Run pipelines end to end into the benchmark directory and write the leaderboard
+fn benchmark(targets: &[String], args: &Args) -> Result<Leaderboard, Failure> {
+
+    let dir = args.output.as_deref().unwrap_or(BENCHMARK_DIR);
+    let mut results = Vec::new();
+    for name in targets {
+        let model = lookup(name).map(|entry| entry.model).unwrap_or("TabularPipeline");
+        let mut config = configure(name, args)?;
+        config.output_dir = format!("{dir}/runs/{}", config.name);
+
+        // Start from an empty directory so artifact sizes only count this run
+        let _ = fs::remove_dir_all(&config.output_dir);
+        println!("Benchmarking {name:?}");
+        results.push(BenchmarkResult::run(name, model, &config, build(name, config.clone())));
+    }
+
+    let leaderboard = Leaderboard::new(results);
+    fs::create_dir_all(dir).map_err(|err| ("benchmark", PipelineError::artifact(dir, err)))?;
+    leaderboard.save(dir).map_err(|err| ("benchmark", err))?;
+    Ok(leaderboard)
+}
+
+
+/// Load the saved models of pipelines and serve predictions until the process is stopped.
+///
+/// Without pipelines, every registered pipeline with a saved version is served.
+fn serve(args: &Args) -> Result<(), Failure> {
+
+    let explicit = !args.pipelines.is_empty();
+    let targets: Vec<String> = match explicit {
+        true => args.pipelines.clone(),
+        false => REGISTRY.iter().map(|entry| entry.name.to_string()).collect()
+    };
+
+    let mut models = Vec::new();
+    for name in &targets {
+        let config = configure(name, args)?;
+        if !explicit && ArtifactStore::new(&config.output_dir).versions().is_ok_and(|versions| versions.is_empty()) {
+            continue;
+        }
+        let model = ServedModel::load(&config).map_err(|err| ("serve", err))?;
+        println!("Loaded {} {} ({})", model.name, VersionSpec::Pinned(model.version), model.stage);
+        models.push(model);
+    }
+    if models.is_empty() {
+        return Err(("serve", PipelineError::Config("no saved models to serve, train a pipeline first".to_string())));
+    }
+
+    let address = format!("127.0.0.1:{}", args.port.unwrap_or(DEFAULT_PORT));
+    let listener = TcpListener::bind(&address).map_err(|err| ("serve", PipelineError::artifact(&address, err)))?;
+    println!("Serving {} models on http://{address}", models.len());
+    ModelServer::new(models).serve(listener).map_err(|err| ("serve", err))
+}
+
+
+/// Search hyperparameters of a pipeline, saving the best model and results table
+fn search(name: &str, args: &Args) -> Result<(), Failure> {
+    let config = configure(name, args)?;
+    let results = tabular::search(config).map_err(|err| ("search", err))?;
+    println!("{}", results.to_markdown());
+    Ok(())
+}
+
 
 fn main() {
 
-    let mut model = TitanicModel::new();
-    model.load_data(0.3);
-    model.train();
-    
+    let raw: Vec<String> = std::env::args().skip(1).collect();
+    let args = match parse_args(&raw) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}");
+            process::exit(2);
+        }
+    };
+
+    let action = match args.command.as_str() {
+        "list" => {
+            for entry in REGISTRY {
+                println!(
+                    "{:<22}{:<22}{:<12}{}",
+                    entry.name,
+                    entry.model,
+                    entry.task().name(),
+                    entry.datasets().join(", ")
+                );
+            }
+            for name in ExperimentConfig::discover() {
+                match ExperimentConfig::from_file(ExperimentConfig::path_for(&name)) {
+                    Ok(config) => println!(
+                        "{:<22}{:<22}{:<12}{}",
+                        name,
+                        "TabularPipeline",
+                        config.task().name(),
+                        config.dataset.path
+                    ),
+                    Err(err) => eprintln!("{err}")
+                }
+            }
+            return;
+        },
+        "load" => Action::Stages(vec![Stage::Load]),
+        "train" => Action::Stages(vec![Stage::Load, Stage::Transform, Stage::Train]),
+        "infer" => Action::Stages(vec![Stage::Load, Stage::Inference]),
+        "evaluate" => Action::Stages(vec![Stage::Load, Stage::Evaluate]),
+        "run-all" => Action::Stages(Stage::ALL.to_vec()),
+        "cross-validate" => Action::CrossValidate,
+        "search" => Action::Search,
+        "versions" => Action::Versions,
+        "promote" => Action::Promote,
+        "prune" => Action::Prune,
+        "export" => Action::Export,
+        "validate" => Action::Validate,
+        "migrate" => Action::Migrate,
+        "inspect" => Action::Inspect,
+        "predict" => Action::Predict,
+        "benchmark" => {
+            let targets: Vec<String> = match args.pipelines.is_empty() {
+                true => REGISTRY.iter().map(|entry| entry.name.to_string()).collect(),
+                false => args.pipelines.clone()
+            };
+            match benchmark(&targets, &args) {
+                Ok(leaderboard) => {
+                    println!("{}", leaderboard.to_markdown());
+                    if leaderboard.has_failures() {
+                        process::exit(1);
+                    }
+                },
+                Err((stage, err)) => {
+                    eprintln!("Benchmark failed during {stage}: {err}");
+                    process::exit(1);
+                }
+            }
+            return;
+        },
+        "serve" => {
+            if let Err((stage, err)) = serve(&args) {
+                eprintln!("Serve failed during {stage}: {err}");
+                process::exit(1);
+            }
+            return;
+        },
+        "help" | "--help" | "-h" => {
+            println!("{USAGE}");
+            return;
+        },
+        other => {
+            eprintln!("Unknown command: {other}\n\n{USAGE}");
+            process::exit(2);
+        }
+    };
+
+    let action = match (action, &args.stages) {
+        (Action::Stages(_), Some(stages)) => Action::Stages(stages.clone()),
+        (action, _) => action
+    };
+
+    let targets: Vec<String> = if args.command == "run-all" && args.pipelines.is_empty() {
+        REGISTRY.iter().map(|entry| entry.name.to_string()).collect()
+    } else {
+        args.pipelines.clone()
+    };
+
+    if targets.is_empty() {
+        eprintln!("No pipeline given for {:?}\n\n{USAGE}", args.command);
+        process::exit(2);
+    }
+
+    let mut failures = Vec::new();
+    for name in &targets {
+        let outcome = match &action {
+            Action::Stages(stages) => run(name, stages, &args),
+            Action::CrossValidate => cross_validate(name, &args),
+            Action::Search => search(name, &args),
+            Action::Versions => versions(name, &args),
+            Action::Promote => promote(name, &args),
+            Action::Prune => prune(name, &args),
+            Action::Export => export(name, &args),
+            Action::Validate => validate(name, &args),
+            Action::Migrate => migrate(name, &args),
+            Action::Inspect => inspect(name, &args),
+            Action::Predict => predict(name, &args)
+        };
+        if let Err((stage, err)) = outcome {
+            eprintln!("Pipeline {name:?} failed during {stage}: {err}");
+            failures.push((name.clone(), stage, err));
+        }
+    }
+
+    if targets.len() > 1 {
+        println!();
+        println!("{:<22}{:<12}detail", "pipeline", "status");
+        for name in &targets {
+            match failures.iter().find(|(failed, _, _)| failed == name) {
+                Some((_, stage, err)) => println!(
+                    "{:<22}{:<12}{} ({stage}): {err}", name, "failed", err.kind()
+                ),
+                None => println!("{:<22}{:<12}", name, "ok")
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        process::exit(1);
+    }
+
 }
@@ -0,0 +1,661 @@
+use ndarray::{Array2, ArrayView1, ArrayView2, Axis};
+use serde::{Serialize, Deserialize};
+
+use crate::Task;
+use crate::export::argmax;
+
+
+/// Smallest probability used when taking logs in log loss
+const PROBABILITY_EPSILON: f64 = 1e-15;
+
+
+/// Names of scalar regression metrics
+pub const REGRESSION_METRICS: &[&str] = &["mse", "rmse", "mae", "r2", "mape"];
+
+/// Names of scalar classification metrics
+pub const CLASSIFICATION_METRICS: &[&str] = &[
+    "accuracy", "log_loss", "roc_auc", "pr_auc",
+    "precision_binary", "precision_macro", "precision_micro", "precision_weighted",
+    "recall_binary", "recall_macro", "recall_micro", "recall_weighted",
+    "f1_binary", "f1_macro", "f1_micro", "f1_weighted"
+];
+
+
+/// Names of scalar metrics reported for a task
+pub fn metric_names(task: Task) -> &'static [&'static str] {
+    match task {
+        Task::Regression => REGRESSION_METRICS,
+        Task::Binary | Task::Multiclass => CLASSIFICATION_METRICS
+    }
+}
+
+
+/// Metrics shown in summary tables for a task
+pub fn headline_metrics(task: Task) -> &'static [&'static str] {
+    match task {
+        Task::Regression => &["rmse", "mae", "r2"],
+        Task::Binary | Task::Multiclass => &["accuracy", "f1_macro", "log_loss"]
+    }
+}
+
+
+/// Whether smaller values of a metric are better
+pub fn lower_is_better(metric: &str) -> bool {
+    matches!(metric, "mse" | "rmse" | "mae" | "mape" | "log_loss")
+}
+
+
+/// Mean squared error between targets and predictions
+pub fn mse(y_true: &ArrayView1<f64>, y_pred: &ArrayView1<f64>) -> f64 {
+    mean(y_true.iter().zip(y_pred).map(|(t, p)| (t - p).powi(2)))
+}
+
+
+/// Root mean squared error between targets and predictions
+pub fn rmse(y_true: &ArrayView1<f64>, y_pred: &ArrayView1<f64>) -> f64 {
+    mse(y_true, y_pred).sqrt()
+}
+
+
+/// Mean absolute error between targets and predictions
+pub fn mae(y_true: &ArrayView1<f64>, y_pred: &ArrayView1<f64>) -> f64 {
+    mean(y_true.iter().zip(y_pred).map(|(t, p)| (t - p).abs()))
+}
+
+
+/// Coefficient of determination (R²).
+///
+/// Constant targets score 1 when predicted exactly and 0 otherwise.
+pub fn r2(y_true: &ArrayView1<f64>, y_pred: &ArrayView1<f64>) -> f64 {
+    let target_mean = mean(y_true.iter().copied());
+    let ss_res: f64 = y_true.iter().zip(y_pred).map(|(t, p)| (t - p).powi(2)).sum();
+    let ss_tot: f64 = y_true.iter().map(|t| (t - target_mean).powi(2)).sum();
+    match (ss_tot == 0.0, ss_res == 0.0) {
+        (true, true) => 1.0,
+        (true, false) => 0.0,
+        (false, _) => 1.0 - ss_res / ss_tot
+    }
+}
+
+
+/// Mean absolute percentage error as a fraction.
+///
+/// Zero targets are guarded with machine epsilon as in scikit-learn.
+pub fn mape(y_true: &ArrayView1<f64>, y_pred: &ArrayView1<f64>) -> f64 {
+    mean(y_true.iter().zip(y_pred).map(|(t, p)| (t - p).abs() / t.abs().max(f64::EPSILON)))
+}
+
+
+/// Metrics reported for regression models
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegressionMetrics {
+
+    /// Mean squared error
+    pub mse: f64,
+
+    /// Root mean squared error
+    pub rmse: f64,
+
+    /// Mean absolute error
+    pub mae: f64,
+
+    /// Coefficient of determination
+    pub r2: f64,
+
+    /// Mean absolute percentage error as a fraction
+    pub mape: f64
+}
+
+
+impl RegressionMetrics {
+
+    /// Compute every regression metric.
+    ///
+    /// # Arguments
+    ///
+    /// * `y_true` - Target values.
+    /// * `y_pred` - Predicted values in the same scale as the targets.
+    ///
+    pub fn compute(y_true: &ArrayView1<f64>, y_pred: &ArrayView1<f64>) -> Self {
+        RegressionMetrics {
+            mse: mse(y_true, y_pred),
+            rmse: rmse(y_true, y_pred),
+            mae: mae(y_true, y_pred),
+            r2: r2(y_true, y_pred),
+            mape: mape(y_true, y_pred)
+        }
+    }
+
+}
+
+
+/// Strategies for averaging per class precision, recall and F1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Average {
+
+    /// Score of class 1 only
+    Binary,
+
+    /// Unweighted mean of per class scores
+    Macro,
+
+    /// Score over global true positive, false positive and false negative counts
+    Micro,
+
+    /// Mean of per class scores weighted by class support
+    Weighted
+}
+
+
+/// Counts of actual (rows) against predicted (columns) classes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfusionMatrix {
+
+    /// Count of rows per actual and predicted class
+    pub counts: Vec<Vec<usize>>
+}
+
+
+impl ConfusionMatrix {
+
+    /// Tally predictions against labels.
+    ///
+    /// # Arguments
+    ///
+    /// * `y_true` - Actual class of each row.
+    /// * `y_pred` - Predicted class of each row.
+    /// * `num_classes` - Number of classes, grown to cover any larger label.
+    ///
+    pub fn new(y_true: &[usize], y_pred: &[usize], num_classes: usize) -> Self {
+        let num_classes = y_true
+            .iter()
+            .chain(y_pred)
+            .map(|&label| label + 1)
+            .fold(num_classes, usize::max);
+        let mut counts = vec![vec![0; num_classes]; num_classes];
+        for (&actual, &predicted) in y_true.iter().zip(y_pred) {
+            counts[actual][predicted] += 1;
+        }
+        ConfusionMatrix { counts }
+    }
+
+    /// Number of classes
+    pub fn num_classes(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Total number of rows
+    pub fn total(&self) -> usize {
+        self.counts.iter().flatten().sum()
+    }
+
+    /// Fraction of rows predicted correctly
+    pub fn accuracy(&self) -> f64 {
+        let correct: usize = (0..self.num_classes()).map(|class| self.counts[class][class]).sum();
+        ratio(correct, self.total())
+    }
+
+    /// Number of rows actually in class
+    pub fn support(&self, class: usize) -> usize {
+        self.counts[class].iter().sum()
+    }
+
+    fn true_positives(&self, class: usize) -> usize {
+        self.counts[class][class]
+    }
+
+    fn predicted(&self, class: usize) -> usize {
+        self.counts.iter().map(|row| row[class]).sum()
+    }
+
+    /// Precision of each class, 0 for classes never predicted
+    pub fn class_precision(&self) -> Vec<f64> {
+        (0..self.num_classes())
+            .map(|class| ratio(self.true_positives(class), self.predicted(class)))
+            .collect()
+    }
+
+    /// Recall of each class, 0 for classes with no rows
+    pub fn class_recall(&self) -> Vec<f64> {
+        (0..self.num_classes())
+            .map(|class| ratio(self.true_positives(class), self.support(class)))
+            .collect()
+    }
+
+    /// F1 score of each class
+    pub fn class_f1(&self) -> Vec<f64> {
+        self.class_precision()
+            .iter()
+            .zip(self.class_recall())
+            .map(|(&p, r)| harmonic_mean(p, r))
+            .collect()
+    }
+
+    /// Precision averaged over classes
+    pub fn precision(&self, average: Average) -> f64 {
+        self.average(average, &self.class_precision())
+    }
+
+    /// Recall averaged over classes
+    pub fn recall(&self, average: Average) -> f64 {
+        self.average(average, &self.class_recall())
+    }
+
+    /// F1 score averaged over classes
+    pub fn f1(&self, average: Average) -> f64 {
+        self.average(average, &self.class_f1())
+    }
+
+    /// Micro averages over a single label per row all reduce to accuracy
+    fn average(&self, average: Average, scores: &[f64]) -> f64 {
+        match average {
+            Average::Binary => scores.get(1).copied().unwrap_or(0.0),
+            Average::Macro => mean(scores.iter().copied()),
+            Average::Micro => self.accuracy(),
+            Average::Weighted => {
+                let total = self.total();
+                scores
+                    .iter()
+                    .enumerate()
+                    .map(|(class, score)| score * ratio(self.support(class), total))
+                    .sum()
+            }
+        }
+    }
+
+}
+
+
+/// Mean negative log likelihood of the actual classes.
+///
+/// Rows of `probabilities` are normalized to sum to 1 and clipped away from
+/// 0 and 1 before taking logs. A class without a probability column was
+/// given probability 0.
+///
+/// # Arguments
+///
+/// * `y_true` - Actual class of each row.
+/// * `probabilities` - Probability of each class per row.
+///
+pub fn log_loss(y_true: &[usize], probabilities: &ArrayView2<f64>) -> f64 {
+    mean(y_true.iter().zip(probabilities.axis_iter(Axis(0))).map(|(&class, row)| {
+        let total: f64 = row.sum();
+        let p = match (total > 0.0, row.get(class)) {
+            (true, Some(&p)) => p / total,
+            _ => 0.0
+        };
+        -p.clamp(PROBABILITY_EPSILON, 1.0 - PROBABILITY_EPSILON).ln()
+    }))
+}
+
+
+/// Area under the ROC curve of binary scores.
+///
+/// Returns `None` when only one class is present.
+///
+/// # Arguments
+///
+/// * `y_true` - Whether each row is in the positive class.
+/// * `scores` - Score of each row, higher meaning more likely positive.
+///
+pub fn roc_auc(y_true: &[bool], scores: &[f64]) -> Option<f64> {
+
+    let (positives, negatives) = class_counts(y_true);
+    if positives == 0 || negatives == 0 {
+        return None;
+    }
+
+    let (mut area, mut tp, mut fp) = (0.0, 0.0, 0.0);
+    for (group_tp, group_fp) in threshold_groups(y_true, scores) {
+        let (next_tp, next_fp) = (tp + group_tp, fp + group_fp);
+        area += (next_fp - fp) * (tp + next_tp) / 2.0;
+        (tp, fp) = (next_tp, next_fp);
+    }
+
+    Some(area / (positives as f64 * negatives as f64))
+}
+
+
+/// Area under the precision recall curve of binary scores.
+///
+/// Computed as average precision (the step-wise area used by
+/// scikit-learn). Returns `None` when there are no positive rows.
+///
+/// # Arguments
+///
+/// * `y_true` - Whether each row is in the positive class.
+/// * `scores` - Score of each row, higher meaning more likely positive.
+///
+pub fn pr_auc(y_true: &[bool], scores: &[f64]) -> Option<f64> {
+
+    let (positives, _) = class_counts(y_true);
+    if positives == 0 {
+        return None;
+    }
+
+    let (mut area, mut tp, mut fp) = (0.0, 0.0, 0.0);
+    for (group_tp, group_fp) in threshold_groups(y_true, scores) {
+        (tp, fp) = (tp + group_tp, fp + group_fp);
+        area += group_tp / positives as f64 * (tp / (tp + fp));
+    }
+
+    Some(area)
+}
+
+
+/// Metrics reported for classification models
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ClassificationMetrics {
+
+    /// Fraction of rows predicted correctly
+    pub accuracy: f64,
+
+    /// Precision per averaging strategy
+    pub precision: AveragedScore,
+
+    /// Recall per averaging strategy
+    pub recall: AveragedScore,
+
+    /// F1 score per averaging strategy
+    pub f1: AveragedScore,
+
+    /// Mean negative log likelihood of the actual classes
+    pub log_loss: f64,
+
+    /// Area under ROC curve (macro one-vs-rest for multiclass)
+    pub roc_auc: Option<f64>,
+
+    /// Area under precision recall curve (macro one-vs-rest for multiclass)
+    pub pr_auc: Option<f64>,
+
+    /// Counts of actual against predicted classes
+    pub confusion_matrix: ConfusionMatrix
+}
+
+
+/// Score averaged over classes with each strategy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AveragedScore {
+
+    /// Score of the positive class (binary problems only)
+    pub binary: Option<f64>,
+
+    /// Unweighted mean of per class scores
+    #[serde(rename = "macro")]
+    pub macro_avg: f64,
+
+    /// Score over global counts
+    #[serde(rename = "micro")]
+    pub micro_avg: f64,
+
+    /// Support weighted mean of per class scores
+    #[serde(rename = "weighted")]
+    pub weighted_avg: f64
+}
+
+
+impl AveragedScore {
+
+    fn from_fn(num_classes: usize, score: impl Fn(Average) -> f64) -> Self {
+        AveragedScore {
+            binary: (num_classes == 2).then(|| score(Average::Binary)),
+            macro_avg: score(Average::Macro),
+            micro_avg: score(Average::Micro),
+            weighted_avg: score(Average::Weighted)
+        }
+    }
+
+}
+
+
+impl ClassificationMetrics {
+
+    /// Compute every classification metric from class probabilities.
+    ///
+    /// Predicted classes are the most probable class of each row. Binary
+    /// problems may pass a single column holding the positive class probability.
+    ///
+    /// # Arguments
+    ///
+    /// * `y_true` - Actual class of each row.
+    /// * `probabilities` - Probability of each class per row.
+    ///
+    pub fn compute(y_true: &[usize], probabilities: &ArrayView2<f64>) -> Self {
+
+        let probabilities = class_probabilities(probabilities);
+        let num_classes = probabilities.ncols();
+        let y_pred: Vec<usize> = probabilities
+            .axis_iter(Axis(0))
+            .map(|row| argmax(&row))
+            .collect();
+
+        let matrix = ConfusionMatrix::new(y_true, &y_pred, num_classes);
+        let num_classes = matrix.num_classes();
+        let (roc_auc, pr_auc) = match num_classes {
+            2 => {
+                let positive: Vec<bool> = y_true.iter().map(|&class| class == 1).collect();
+                let scores = probabilities.column(1).to_vec();
+                (roc_auc(&positive, &scores), pr_auc(&positive, &scores))
+            },
+            _ => (
+                one_vs_rest(y_true, &probabilities.view(), roc_auc),
+                one_vs_rest(y_true, &probabilities.view(), pr_auc)
+            )
+        };
+
+        ClassificationMetrics {
+            accuracy: matrix.accuracy(),
+            precision: AveragedScore::from_fn(num_classes, |avg| matrix.precision(avg)),
+            recall: AveragedScore::from_fn(num_classes, |avg| matrix.recall(avg)),
+            f1: AveragedScore::from_fn(num_classes, |avg| matrix.f1(avg)),
+            log_loss: log_loss(y_true, &probabilities.view()),
+            roc_auc,
+            pr_auc,
+            confusion_matrix: matrix
+        }
+    }
+
+}
+
+
+/// Expand a single positive class column into two class columns
+fn class_probabilities(probabilities: &ArrayView2<f64>) -> Array2<f64> {
+    match probabilities.ncols() {
+        1 => {
+            let mut expanded = Array2::zeros((probabilities.nrows(), 2));
+            for (idx, &p) in probabilities.column(0).iter().enumerate() {
+                expanded[[idx, 0]] = 1.0 - p;
+                expanded[[idx, 1]] = p;
+            }
+            expanded
+        },
+        _ => probabilities.to_owned()
+    }
+}
+
+
+/// Macro average of a binary curve metric over each class against the rest
+fn one_vs_rest(
+    y_true: &[usize],
+    probabilities: &ArrayView2<f64>,
+    metric: fn(&[bool], &[f64]) -> Option<f64>) -> Option<f64> {
+
+    let scores: Vec<f64> = (0..probabilities.ncols())
+        .filter_map(|class| {
+            let positive: Vec<bool> = y_true.iter().map(|&label| label == class).collect();
+            metric(&positive, &probabilities.column(class).to_vec())
+        })
+        .collect();
+
+    match scores.is_empty() {
+        true => None,
+        false => Some(mean(scores.into_iter()))
+    }
+}
+
+
+/// True and false positives gained at each distinct score, highest score first
+fn threshold_groups(y_true: &[bool], scores: &[f64]) -> Vec<(f64, f64)> {
+
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+
+    let mut groups: Vec<(f64, f64)> = Vec::new();
+    let mut previous: Option<f64> = None;
+    for idx in order {
+        if previous != Some(scores[idx]) {
+            groups.push((0.0, 0.0));
+            previous = Some(scores[idx]);
+        }
+        let group = groups.last_mut().unwrap();
+        match y_true[idx] {
+            true => group.0 += 1.0,
+            false => group.1 += 1.0
+        }
+    }
+    groups
+}
+
+
+fn class_counts(y_true: &[bool]) -> (usize, usize) {
+    let positives = y_true.iter().filter(|&&positive| positive).count();
+    (positives, y_true.len() - positives)
+}
+
+
+fn harmonic_mean(a: f64, b: f64) -> f64 {
+    if a + b == 0.0 {
+        return 0.0;
+    }
+    2.0 * a * b / (a + b)
+}
+
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    match denominator {
+        0 => 0.0,
+        _ => numerator as f64 / denominator as f64
+    }
+}
+
+
+fn mean(values: impl Iterator<Item = f64>) -> f64 {
+    let (total, count) = values.fold((0.0, 0usize), |(total, count), v| (total + v, count + 1));
+    match count {
+        0 => f64::NAN,
+        _ => total / count as f64
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use ndarray::array;
+
+    use super::*;
+
+    fn assert_close(found: f64, expected: f64) {
+        assert!((found - expected).abs() < 1e-6, "{found} != {expected}");
+    }
+
+    #[test]
+    fn regression_metrics_match_reference() {
+        // scikit-learn's documented example
+        let y_true = array![3.0, -0.5, 2.0, 7.0];
+        let y_pred = array![2.5, 0.0, 2.0, 8.0];
+        let metrics = RegressionMetrics::compute(&y_true.view(), &y_pred.view());
+        assert_close(metrics.mse, 0.375);
+        assert_close(metrics.rmse, 0.375f64.sqrt());
+        assert_close(metrics.mae, 0.5);
+        assert_close(metrics.r2, 0.948608);
+        assert_close(metrics.mape, (0.5 / 3.0 + 1.0 + 0.0 + 1.0 / 7.0) / 4.0);
+    }
+
+    #[test]
+    fn r2_of_constant_targets() {
+        let y_true = array![2.0, 2.0, 2.0];
+        assert_eq!(r2(&y_true.view(), &array![2.0, 2.0, 2.0].view()), 1.0);
+        assert_eq!(r2(&y_true.view(), &array![2.0, 2.0, 3.0].view()), 0.0);
+
+        // Predicting the mean scores 0 and worse than the mean goes negative
+        let y_true = array![1.0, 2.0, 3.0];
+        assert_close(r2(&y_true.view(), &array![2.0, 2.0, 2.0].view()), 0.0);
+        assert_close(r2(&y_true.view(), &array![3.0, 2.0, 1.0].view()), -3.0);
+    }
+
+    #[test]
+    fn mape_guards_zero_targets() {
+        let y_true = array![0.0, 2.0];
+        assert_close(mape(&y_true.view(), &array![0.0, 1.0].view()), 0.25);
+
+        let missed = mape(&y_true.view(), &array![1.0, 2.0].view());
+        assert!(missed.is_finite());
+        assert_eq!(missed, 0.5 / f64::EPSILON);
+    }
+
+    #[test]
+    fn roc_auc_matches_reference() {
+        // roc_auc_score([0, 0, 1, 1], [0.1, 0.4, 0.35, 0.8])
+        assert_close(roc_auc(&[false, false, true, true], &[0.1, 0.4, 0.35, 0.8]).unwrap(), 0.75);
+
+        // Tied scores count half: positives (0.5, 0.9) against negatives (0.5, 0.2)
+        assert_close(roc_auc(&[false, true, false, true], &[0.5, 0.5, 0.2, 0.9]).unwrap(), 0.875);
+
+        assert_eq!(roc_auc(&[true, true], &[0.2, 0.7]), None);
+    }
+
+    #[test]
+    fn average_precision_matches_reference() {
+        // average_precision_score([0, 0, 1, 1], [0.1, 0.4, 0.35, 0.8]) = (1 + 2/3) / 2
+        assert_close(pr_auc(&[false, false, true, true], &[0.1, 0.4, 0.35, 0.8]).unwrap(), 5.0 / 6.0);
+        assert_eq!(pr_auc(&[false, false], &[0.1, 0.4]), None);
+    }
+
+    #[test]
+    fn log_loss_matches_reference() {
+        // log_loss(["spam", "ham", "ham", "spam"], [[.1, .9], [.9, .1], [.8, .2], [.35, .65]])
+        let probabilities = array![[0.1, 0.9], [0.9, 0.1], [0.8, 0.2], [0.35, 0.65]];
+        assert_close(log_loss(&[1, 0, 0, 1], &probabilities.view()), 0.21616187);
+
+        // A label without a probability column scores as probability 0
+        let clipped = -PROBABILITY_EPSILON.ln();
+        assert_close(log_loss(&[2], &array![[0.5, 0.5]].view()), clipped);
+    }
+
+    #[test]
+    fn averages_match_reference() {
+        // precision_score([0, 1, 2, 0, 1, 2], [0, 2, 1, 0, 0, 1], average=...)
+        let matrix = ConfusionMatrix::new(&[0, 1, 2, 0, 1, 2], &[0, 2, 1, 0, 0, 1], 3);
+        assert_close(matrix.precision(Average::Macro), 2.0 / 9.0);
+        assert_close(matrix.precision(Average::Micro), 1.0 / 3.0);
+        assert_close(matrix.precision(Average::Weighted), 2.0 / 9.0);
+        assert_close(matrix.recall(Average::Macro), 1.0 / 3.0);
+        assert_close(matrix.recall(Average::Weighted), 1.0 / 3.0);
+        assert_close(matrix.f1(Average::Macro), 4.0 / 15.0);
+        assert_close(matrix.f1(Average::Micro), 1.0 / 3.0);
+        assert_close(matrix.f1(Average::Weighted), 4.0 / 15.0);
+
+        // Weighted averages follow class support: recall 1 on 3 rows, 1/2 on 2 rows
+        let matrix = ConfusionMatrix::new(&[0, 0, 0, 1, 1], &[0, 0, 0, 1, 0], 2);
+        assert_close(matrix.recall(Average::Weighted), 0.8);
+        assert_close(matrix.recall(Average::Macro), 0.75);
+        assert_close(matrix.precision(Average::Binary), 1.0);
+        assert_close(matrix.f1(Average::Binary), 2.0 / 3.0);
+    }
+
+    #[test]
+    fn unseen_labels_grow_the_matrix() {
+        let matrix = ConfusionMatrix::new(&[0, 1, 2], &[0, 1, 3], 2);
+        assert_eq!(matrix.num_classes(), 4);
+        assert_eq!(matrix.total(), 3);
+        assert_close(matrix.accuracy(), 2.0 / 3.0);
+
+        // A test row of a class the model never saw
+        let metrics = ClassificationMetrics::compute(&[0, 1, 2], &array![[0.9, 0.1], [0.2, 0.8], [0.6, 0.4]].view());
+        assert_eq!(metrics.confusion_matrix.num_classes(), 3);
+        assert_eq!(metrics.precision.binary, None);
+    }
+
+}
+
@@ -0,0 +1,614 @@
+use std::collections::BTreeMap;
+use std::fs;
+
+use ndarray::{Array2, Axis};
+
+use crate::Task;
+use crate::error::*;
+use crate::export::{argmax, class_probabilities, ExportedModel};
+use crate::preprocessing::FittedEncoder;
+
+
+/// File an exported model is written to in ONNX format
+pub const ONNX_FILE: &str = "model.onnx";
+
+/// Name of the graph input holding raw feature rows
+pub const FEATURES_INPUT: &str = "features";
+
+/// ONNX IR version written
+const IR_VERSION: u64 = 7;
+
+/// Version of the default ONNX operator set the graph uses
+const OPSET_VERSION: u64 = 13;
+
+/// `TensorProto.DataType` of 32 bit floats
+const FLOAT: u64 = 1;
+
+/// `TensorProto.DataType` of 64 bit integers
+const INT64: u64 = 7;
+
+/// `AttributeProto.AttributeType` of integer attributes
+const ATTRIBUTE_INT: u64 = 2;
+
+
+/// Constant tensor stored in the graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct Initializer {
+
+    /// Name nodes refer to the tensor by
+    pub name: String,
+
+    /// Dimensions of the tensor
+    pub dims: Vec<usize>,
+
+    /// Values in row major order
+    pub values: Vec<f32>
+}
+
+
+/// Operation in the graph
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+
+    /// ONNX operator type, e.g. `MatMul`
+    pub op_type: String,
+
+    /// Names of the values the operator reads
+    pub inputs: Vec<String>,
+
+    /// Names of the values the operator writes
+    pub outputs: Vec<String>,
+
+    /// Integer attributes of the operator
+    pub attributes: Vec<(String, i64)>
+}
+
+
+/// Graph input or output with a `[batch, width]` shape
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValueInfo {
+
+    /// Name of value
+    pub name: String,
+
+    /// `TensorProto.DataType` of the value
+    pub elem_type: u64,
+
+    /// Number of columns
+    pub width: usize
+}
+
+
+/// ONNX model of an exported linear or logistic model.
+///
+/// The graph reads raw features, applies the fitted feature scaling, the
+/// linear layer and the link function, and for regression maps outputs
+/// back to the target scale. Only the operators needed for that are
+/// written, and [`OnnxModel::run`] evaluates them in process.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OnnxModel {
+
+    /// Name of graph
+    pub name: String,
+
+    /// Operations in execution order
+    pub nodes: Vec<Node>,
+
+    /// Constant tensors
+    pub initializers: Vec<Initializer>,
+
+    /// Graph inputs
+    pub inputs: Vec<ValueInfo>,
+
+    /// Graph outputs
+    pub outputs: Vec<ValueInfo>,
+
+    /// Key value metadata, e.g. the feature names
+    pub metadata: Vec<(String, String)>
+}
+
+
+impl OnnxModel {
+
+    /// Build the ONNX graph of an exported model.
+    ///
+    /// Regression graphs output `prediction`. Classifier graphs output
+    /// `probabilities` (the positive class for binary models, one column
+    /// per class otherwise) and the predicted `label`.
+    pub fn from_export(exported: &ExportedModel) -> PipelineResult<Self> {
+
+        let preprocessing = &exported.preprocessing;
+        let features = preprocessing.features.len();
+        let classes = exported.bias.len();
+        let mut model = OnnxModel {
+            name: preprocessing.target.clone(),
+            nodes: Vec::new(),
+            initializers: Vec::new(),
+            inputs: vec![ValueInfo { name: FEATURES_INPUT.to_string(), elem_type: FLOAT, width: features }],
+            outputs: Vec::new(),
+            metadata: vec![
+                ("features".to_string(), preprocessing.features.join(",")),
+                ("target".to_string(), preprocessing.target.clone()),
+                ("model".to_string(), format!("{:?}", exported.model).to_lowercase()),
+                ("task".to_string(), exported.task.name().to_string())
+            ]
+        };
+
+        let scaled = match &preprocessing.x_encode {
+            FittedEncoder::None => FEATURES_INPUT.to_string(),
+            FittedEncoder::Standard { mean, stdev } => {
+                let centered = model.binary("Sub", FEATURES_INPUT, "feature_mean", mean.clone(), "centered");
+                model.binary("Div", &centered, "feature_scale", nonzero(stdev), "scaled")
+            },
+            FittedEncoder::MinMax { min, max } => {
+                let shifted = model.binary("Sub", FEATURES_INPUT, "feature_min", min.clone(), "shifted");
+                let range = max.iter().zip(min).map(|(max, min)| max - min).collect::<Vec<_>>();
+                model.binary("Div", &shifted, "feature_range", nonzero(&range), "scaled")
+            },
+            FittedEncoder::OneHot { .. } => {
+                return Err(PipelineError::Config("one hot feature encoding cannot be exported to ONNX".to_string()));
+            }
+        };
+
+        let weights: Vec<f64> = exported.weights.iter().flatten().copied().collect();
+        model.initializers.push(initializer("weights", vec![features, classes], &weights));
+        model.node("MatMul", &[&scaled, "weights"], "product", &[]);
+        let logits = model.binary("Add", "product", "bias", exported.bias.clone(), "logits");
+
+        match exported.task {
+            Task::Regression => {
+                let (scale, offset) = match &preprocessing.y_encode {
+                    FittedEncoder::None => (vec![1.0], vec![0.0]),
+                    FittedEncoder::Standard { mean, stdev } => (nonzero(stdev), mean.clone()),
+                    FittedEncoder::MinMax { min, max } => (nonzero(&[max[0] - min[0]]), min.clone()),
+                    FittedEncoder::OneHot { .. } => {
+                        return Err(PipelineError::Config("one hot targets cannot be regressed".to_string()));
+                    }
+                };
+                // Scaled targets are mapped back to the original scale
+                let rescaled = model.binary("Mul", &logits, "target_scale", scale, "rescaled");
+                model.binary("Add", &rescaled, "target_offset", offset, "prediction");
+                model.outputs.push(ValueInfo { name: "prediction".to_string(), elem_type: FLOAT, width: 1 });
+            },
+            Task::Binary => {
+                model.node("Sigmoid", &[&logits], "probabilities", &[]);
+                let positive = model.binary("Greater", "probabilities", "threshold", vec![0.5], "positive");
+                model.node("Cast", &[&positive], "label", &[("to", INT64 as i64)]);
+                model.outputs.push(ValueInfo { name: "probabilities".to_string(), elem_type: FLOAT, width: 1 });
+                model.outputs.push(ValueInfo { name: "label".to_string(), elem_type: INT64, width: 1 });
+            },
+            Task::Multiclass => {
+                model.node("Softmax", &[&logits], "probabilities", &[("axis", 1)]);
+                model.node("ArgMax", &["probabilities"], "label", &[("axis", 1), ("keepdims", 1)]);
+                model.outputs.push(ValueInfo { name: "probabilities".to_string(), elem_type: FLOAT, width: classes });
+                model.outputs.push(ValueInfo { name: "label".to_string(), elem_type: INT64, width: 1 });
+            }
+        }
+
+        Ok(model)
+    }
+
+    /// Save model to a directory in ONNX protobuf format
+    pub fn save(&self, dir: &str) -> PipelineResult<()> {
+        let path = format!("{dir}/{ONNX_FILE}");
+        fs::create_dir_all(dir).map_err(|err| PipelineError::artifact(dir, err))?;
+        fs::write(&path, self.encode()).map_err(|err| PipelineError::artifact(&path, err))
+    }
+
+    /// Load model saved in ONNX protobuf format
+    pub fn load(dir: &str) -> PipelineResult<Self> {
+        let path = format!("{dir}/{ONNX_FILE}");
+        let bytes = fs::read(&path).map_err(|err| PipelineError::artifact(&path, err))?;
+        OnnxModel::decode(&bytes).map_err(|message| PipelineError::InvalidArtifact { path, message })
+    }
+
+    /// Evaluate graph on raw feature rows, returning every graph output by name.
+    ///
+    /// Values are computed as 32 bit floats like an ONNX runtime would.
+    pub fn run(&self, x: &Array2<f64>) -> PipelineResult<BTreeMap<String, Array2<f64>>> {
+
+        let width = self.inputs.first().map_or(0, |input| input.width);
+        if x.ncols() != width {
+            return Err(PipelineError::ShapeMismatch {
+                context: format!("ONNX graph {}", self.name),
+                expected: format!("{width} feature columns"),
+                found: format!("{} columns", x.ncols())
+            });
+        }
+
+        let mut values: BTreeMap<String, Array2<f64>> = BTreeMap::new();
+        values.insert(FEATURES_INPUT.to_string(), x.mapv(round));
+        for tensor in &self.initializers {
+            let (rows, cols) = match tensor.dims.as_slice() {
+                [rows, cols] => (*rows, *cols),
+                [cols] => (1, *cols),
+                dims => return Err(unsupported(&format!("initializer {} with dims {dims:?}", tensor.name)))
+            };
+            let data = tensor.values.iter().map(|&v| v as f64).collect();
+            let array = Array2::from_shape_vec((rows, cols), data).map_err(|err| unsupported(&err.to_string()))?;
+            values.insert(tensor.name.clone(), array);
+        }
+
+        for node in &self.nodes {
+            let input = |idx: usize| -> PipelineResult<&Array2<f64>> {
+                let name = node.inputs.get(idx).ok_or(unsupported(&format!("{} without input {idx}", node.op_type)))?;
+                values.get(name).ok_or(unsupported(&format!("{} reads undefined value {name:?}", node.op_type)))
+            };
+            let attribute = |name: &str| node.attributes.iter().find(|(key, _)| key == name).map(|(_, value)| *value);
+
+            let output = match node.op_type.as_str() {
+                "Sub" => (input(0)? - input(1)?).mapv(round),
+                "Div" => (input(0)? / input(1)?).mapv(round),
+                "Mul" => (input(0)? * input(1)?).mapv(round),
+                "Add" => (input(0)? + input(1)?).mapv(round),
+                "MatMul" => input(0)?.dot(input(1)?).mapv(round),
+                "Greater" => (input(0)? - input(1)?).mapv(|diff| if diff > 0.0 { 1.0 } else { 0.0 }),
+                "Cast" => input(0)?.mapv(f64::round),
+                "Sigmoid" => input(0)?.mapv(|z| round(1.0 / (1.0 + (-z).exp()))),
+                "Softmax" => {
+                    if attribute("axis") != Some(1) {
+                        return Err(unsupported("Softmax over an axis other than 1"));
+                    }
+                    // A single logit column is its own softmax denominator
+                    match input(0)?.ncols() {
+                        1 => Array2::ones(input(0)?.raw_dim()),
+                        _ => class_probabilities(input(0)?.clone()).mapv(round)
+                    }
+                },
+                "ArgMax" => {
+                    if attribute("axis") != Some(1) || attribute("keepdims").unwrap_or(1) != 1 {
+                        return Err(unsupported("ArgMax other than over axis 1 keeping dims"));
+                    }
+                    let labels = input(0)?
+                        .axis_iter(Axis(0))
+                        .map(|row| argmax(&row) as f64)
+                        .collect::<Vec<_>>();
+                    Array2::from_shape_vec((labels.len(), 1), labels).map_err(|err| unsupported(&err.to_string()))?
+                },
+                other => return Err(unsupported(&format!("operator {other}")))
+            };
+
+            let name = node.outputs.first().ok_or(unsupported(&format!("{} without output", node.op_type)))?;
+            values.insert(name.clone(), output);
+        }
+
+        self.outputs
+            .iter()
+            .map(|output| match values.remove(&output.name) {
+                Some(value) => Ok((output.name.clone(), value)),
+                None => Err(unsupported(&format!("graph output {:?} is never computed", output.name)))
+            })
+            .collect()
+    }
+
+    /// Append a node reading a value and a new initializer, returning its output name
+    fn binary(&mut self, op_type: &str, input: &str, constant: &str, values: Vec<f64>, output: &str) -> String {
+        self.initializers.push(initializer(constant, vec![values.len()], &values));
+        self.node(op_type, &[input, constant], output, &[]);
+        output.to_string()
+    }
+
+    fn node(&mut self, op_type: &str, inputs: &[&str], output: &str, attributes: &[(&str, i64)]) {
+        self.nodes.push(Node {
+            op_type: op_type.to_string(),
+            inputs: inputs.iter().map(|input| input.to_string()).collect(),
+            outputs: vec![output.to_string()],
+            attributes: attributes.iter().map(|(name, value)| (name.to_string(), *value)).collect()
+        });
+    }
+
+    /// Serialize as an ONNX `ModelProto`
+    pub fn encode(&self) -> Vec<u8> {
+
+        let mut graph = Vec::new();
+        for node in &self.nodes {
+            let mut proto = Vec::new();
+            for input in &node.inputs {
+                put_bytes(&mut proto, 1, input.as_bytes());
+            }
+            for output in &node.outputs {
+                put_bytes(&mut proto, 2, output.as_bytes());
+            }
+            put_bytes(&mut proto, 4, node.op_type.as_bytes());
+            for (name, value) in &node.attributes {
+                let mut attribute = Vec::new();
+                put_bytes(&mut attribute, 1, name.as_bytes());
+                put_varint_field(&mut attribute, 3, *value as u64);
+                put_varint_field(&mut attribute, 20, ATTRIBUTE_INT);
+                put_bytes(&mut proto, 5, &attribute);
+            }
+            put_bytes(&mut graph, 1, &proto);
+        }
+        put_bytes(&mut graph, 2, self.name.as_bytes());
+        for tensor in &self.initializers {
+            let mut proto = Vec::new();
+            for dim in &tensor.dims {
+                put_varint_field(&mut proto, 1, *dim as u64);
+            }
+            put_varint_field(&mut proto, 2, FLOAT);
+            put_bytes(&mut proto, 8, tensor.name.as_bytes());
+            let raw: Vec<u8> = tensor.values.iter().flat_map(|value| value.to_le_bytes()).collect();
+            put_bytes(&mut proto, 9, &raw);
+            put_bytes(&mut graph, 5, &proto);
+        }
+        for (field, values) in [(11, &self.inputs), (12, &self.outputs)] {
+            for value in values {
+                put_bytes(&mut graph, field, &encode_value_info(value));
+            }
+        }
+
+        let mut model = Vec::new();
+        put_varint_field(&mut model, 1, IR_VERSION);
+        put_bytes(&mut model, 2, b"dendritic_ml_models");
+        put_bytes(&mut model, 3, env!("CARGO_PKG_VERSION").as_bytes());
+        put_bytes(&mut model, 7, &graph);
+        let mut opset = Vec::new();
+        put_bytes(&mut opset, 1, b"");
+        put_varint_field(&mut opset, 2, OPSET_VERSION);
+        put_bytes(&mut model, 8, &opset);
+        for (key, value) in &self.metadata {
+            let mut entry = Vec::new();
+            put_bytes(&mut entry, 1, key.as_bytes());
+            put_bytes(&mut entry, 2, value.as_bytes());
+            put_bytes(&mut model, 14, &entry);
+        }
+        model
+    }
+
+    /// Parse an ONNX `ModelProto` holding the fields this module writes
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+
+        let mut model = OnnxModel {
+            name: String::new(),
+            nodes: Vec::new(),
+            initializers: Vec::new(),
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            metadata: Vec::new()
+        };
+
+        for (field, value) in fields(bytes)? {
+            match (field, value) {
+                (8, Field::Bytes(opset)) => {
+                    for (field, value) in fields(opset)? {
+                        if let (2, Field::Varint(version)) = (field, value)
+                            && version > OPSET_VERSION {
+                            return Err(format!("opset {version} is newer than the supported {OPSET_VERSION}"));
+                        }
+                    }
+                },
+                (14, Field::Bytes(entry)) => {
+                    let entry = fields(entry)?;
+                    model.metadata.push((string(&entry, 1)?, string(&entry, 2)?));
+                },
+                (7, Field::Bytes(graph)) => {
+                    for (field, value) in fields(graph)? {
+                        let Field::Bytes(message) = value else { continue };
+                        match field {
+                            1 => model.nodes.push(decode_node(message)?),
+                            2 => model.name = String::from_utf8_lossy(message).to_string(),
+                            5 => model.initializers.push(decode_initializer(message)?),
+                            11 => model.inputs.push(decode_value_info(message)?),
+                            12 => model.outputs.push(decode_value_info(message)?),
+                            _ => {}
+                        }
+                    }
+                },
+                _ => {}
+            }
+        }
+        Ok(model)
+    }
+
+}
+
+
+fn initializer(name: &str, dims: Vec<usize>, values: &[f64]) -> Initializer {
+    Initializer {
+        name: name.to_string(),
+        dims,
+        values: values.iter().map(|&value| value as f32).collect()
+    }
+}
+
+
+/// Scales with zeros replaced by one, as the fitted encoders do
+fn nonzero(scales: &[f64]) -> Vec<f64> {
+    scales.iter().map(|&scale| if scale == 0.0 { 1.0 } else { scale }).collect()
+}
+
+
+/// Round to the nearest 32 bit float
+fn round(value: f64) -> f64 {
+    value as f32 as f64
+}
+
+
+fn unsupported(what: &str) -> PipelineError {
+    PipelineError::Config(format!("unsupported ONNX graph: {what}"))
+}
+
+
+fn encode_value_info(value: &ValueInfo) -> Vec<u8> {
+
+    let mut batch = Vec::new();
+    put_bytes(&mut batch, 2, b"batch");
+    let mut width = Vec::new();
+    put_varint_field(&mut width, 1, value.width as u64);
+    let mut shape = Vec::new();
+    put_bytes(&mut shape, 1, &batch);
+    put_bytes(&mut shape, 1, &width);
+
+    let mut tensor = Vec::new();
+    put_varint_field(&mut tensor, 1, value.elem_type);
+    put_bytes(&mut tensor, 2, &shape);
+    let mut type_proto = Vec::new();
+    put_bytes(&mut type_proto, 1, &tensor);
+
+    let mut proto = Vec::new();
+    put_bytes(&mut proto, 1, value.name.as_bytes());
+    put_bytes(&mut proto, 2, &type_proto);
+    proto
+}
+
+
+fn decode_value_info(bytes: &[u8]) -> Result<ValueInfo, String> {
+
+    let proto = fields(bytes)?;
+    let mut value = ValueInfo { name: string(&proto, 1)?, elem_type: FLOAT, width: 0 };
+    for type_proto in messages(&proto, 2) {
+        for tensor in messages(&fields(type_proto)?, 1) {
+            let tensor = fields(tensor)?;
+            value.elem_type = varint(&tensor, 1).unwrap_or(FLOAT);
+            for shape in messages(&tensor, 2) {
+                let dims = fields(shape)?;
+                if let Some(last) = messages(&dims, 1).last() {
+                    value.width = varint(&fields(last)?, 1).unwrap_or(0) as usize;
+                }
+            }
+        }
+    }
+    Ok(value)
+}
+
+
+fn decode_node(bytes: &[u8]) -> Result<Node, String> {
+
+    let proto = fields(bytes)?;
+    let text = |field: u32| -> Vec<String> {
+        messages(&proto, field).map(|value| String::from_utf8_lossy(value).to_string()).collect()
+    };
+
+    let mut attributes = Vec::new();
+    for attribute in messages(&proto, 5) {
+        let attribute = fields(attribute)?;
+        let value = varint(&attribute, 3).ok_or("only integer attributes are supported")?;
+        attributes.push((string(&attribute, 1)?, value as i64));
+    }
+
+    Ok(Node { op_type: string(&proto, 4)?, inputs: text(1), outputs: text(2), attributes })
+}
+
+
+fn decode_initializer(bytes: &[u8]) -> Result<Initializer, String> {
+
+    let proto = fields(bytes)?;
+    let name = string(&proto, 8)?;
+    if varint(&proto, 2) != Some(FLOAT) {
+        return Err(format!("initializer {name} is not a float tensor"));
+    }
+
+    let dims = proto
+        .iter()
+        .filter_map(|(field, value)| match (field, value) {
+            (1, Field::Varint(dim)) => Some(*dim as usize),
+            _ => None
+        })
+        .collect();
+    let raw = messages(&proto, 9).next().unwrap_or_default();
+    let values = raw
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect();
+    Ok(Initializer { name, dims, values })
+}
+
+
+/// Protobuf field value
+enum Field<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed
+}
+
+
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+
+fn put_varint_field(buf: &mut Vec<u8>, field: u32, value: u64) {
+    put_varint(buf, (field as u64) << 3);
+    put_varint(buf, value);
+}
+
+
+fn put_bytes(buf: &mut Vec<u8>, field: u32, bytes: &[u8]) {
+    put_varint(buf, ((field as u64) << 3) | 2);
+    put_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+
+fn read_varint(bytes: &[u8], pos: &mut usize) -> Result<u64, String> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*pos).ok_or("truncated varint")?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte < 0x80 {
+            return Ok(value);
+        }
+    }
+    Err("varint longer than 64 bits".to_string())
+}
+
+
+/// Fields of a protobuf message in the order they appear
+fn fields(bytes: &[u8]) -> Result<Vec<(u32, Field<'_>)>, String> {
+
+    let mut parsed = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let key = read_varint(bytes, &mut pos)?;
+        let field = (key >> 3) as u32;
+        let value = match key & 7 {
+            0 => Field::Varint(read_varint(bytes, &mut pos)?),
+            1 | 5 => {
+                pos += if key & 7 == 1 { 8 } else { 4 };
+                Field::Fixed
+            },
+            2 => {
+                let len = read_varint(bytes, &mut pos)? as usize;
+                let value = bytes.get(pos..pos + len).ok_or("truncated field")?;
+                pos += len;
+                Field::Bytes(value)
+            },
+            wire => return Err(format!("unsupported wire type {wire}"))
+        };
+        parsed.push((field, value));
+    }
+    Ok(parsed)
+}
+
+
+fn messages<'a>(fields: &[(u32, Field<'a>)], number: u32) -> impl Iterator<Item = &'a [u8]> {
+    fields
+        .iter()
+        .filter_map(move |(field, value)| match value {
+            Field::Bytes(bytes) if *field == number => Some(*bytes),
+            _ => None
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+
+fn varint(fields: &[(u32, Field<'_>)], number: u32) -> Option<u64> {
+    fields.iter().rev().find_map(|(field, value)| match value {
+        Field::Varint(value) if *field == number => Some(*value),
+        _ => None
+    })
+}
+
+
+fn string(fields: &[(u32, Field<'_>)], number: u32) -> Result<String, String> {
+    messages(fields, number)
+        .last()
+        .map(|bytes| String::from_utf8_lossy(bytes).to_string())
+        .ok_or(format!("missing string field {number}"))
+}
@@ -0,0 +1,113 @@
+use dendritic::optimizer::model::ModelPipeline;
+
+use crate::error::PipelineResult;
+
+
+/// Stages that make up the lifecycle of a model pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Load,
+    Transform,
+    Train,
+    Evaluate,
+    Inference
+}
+
+
+impl Stage {
+
+    /// Every stage in the order it runs in
+    pub const ALL: [Stage; 5] = [
+        Stage::Load,
+        Stage::Transform,
+        Stage::Train,
+        Stage::Evaluate,
+        Stage::Inference
+    ];
+
+    /// Parse stage from its command line name
+    pub fn parse(value: &str) -> Option<Stage> {
+        match value {
+            "load" => Some(Stage::Load),
+            "transform" => Some(Stage::Transform),
+            "train" => Some(Stage::Train),
+            "evaluate" | "eval" => Some(Stage::Evaluate),
+            "inference" | "infer" => Some(Stage::Inference),
+            _ => None
+        }
+    }
+
+    /// Command line name of stage
+    pub fn name(&self) -> &'static str {
+        match self {
+            Stage::Load => "load",
+            Stage::Transform => "transform",
+            Stage::Train => "train",
+            Stage::Evaluate => "evaluate",
+            Stage::Inference => "inference"
+        }
+    }
+
+}
+
+
+/// Fallible entry points for every stage of a pipeline
+pub trait Stages {
+
+    /// Load dataset into the pipeline
+    fn try_load(&mut self) -> PipelineResult<()>;
+
+    /// Transform loaded data into model inputs
+    fn try_transform(&mut self) -> PipelineResult<()>;
+
+    /// Train and save model on transformed data
+    fn try_train(&mut self) -> PipelineResult<()>;
+
+    /// Score saved model on held out data and write evaluation reports
+    fn try_evaluate(&mut self) -> PipelineResult<()>;
+
+    /// Predict with saved model
+    fn try_inference(&mut self) -> PipelineResult<()>;
+
+}
+
+
+/// Object safe handle for running the stages of any pipeline
+pub trait Pipeline {
+
+    /// Run a single stage of the pipeline
+    fn run_stage(&mut self, stage: Stage) -> PipelineResult<()>;
+
+    /// Run a sequence of stages in the order given, stopping at the first failure
+    fn run_stages(&mut self, stages: &[Stage]) -> PipelineResult<()> {
+        for stage in stages {
+            self.run_stage(*stage)?;
+        }
+        Ok(())
+    }
+
+}
+
+
+impl<T: Stages> Pipeline for T {
+
+    fn run_stage(&mut self, stage: Stage) -> PipelineResult<()> {
+        match stage {
+            Stage::Load => self.try_load(),
+            Stage::Transform => self.try_transform(),
+            Stage::Train => self.try_train(),
+            Stage::Evaluate => self.try_evaluate(),
+            Stage::Inference => self.try_inference()
+        }
+    }
+
+}
+
+
+/// Evaluation step for model pipelines, alongside dendritic's
+/// `Load`, `Transform`, `Train` and `Inference` traits
+pub trait Evaluate: ModelPipeline {
+
+    /// Score trained model on held out data
+    fn evaluate(&mut self);
+}
@@ -0,0 +1,349 @@
+use ndarray::{Array2, ArrayView2, Axis};
+use serde::{Serialize, Deserialize};
+use dendritic::preprocessing::prelude::*;
+
+use crate::artifact::{read_json, write_json};
+use crate::config::EncoderKind;
+use crate::error::*;
+use crate::export::argmax;
+
+
+/// File preprocessing state is saved to inside a model artifact directory
+pub const PREPROCESSING_FILE: &str = "preprocessing.json";
+
+
+/// Fitted state of a feature encoder that can be saved with a model.
+///
+/// Encoders are fit with dendritic and their statistics captured so the
+/// exact training time transform can be re-applied to new data.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FittedEncoder {
+
+    /// Data is passed through unchanged
+    None,
+
+    /// Standard scalar statistics per column
+    Standard {
+        mean: Vec<f64>,
+        stdev: Vec<f64>
+    },
+
+    /// Min max scalar ranges per column
+    MinMax {
+        min: Vec<f64>,
+        max: Vec<f64>
+    },
+
+    /// One hot encoder over class labels `0..num_classes`
+    OneHot {
+        num_classes: usize
+    }
+}
+
+
+impl FittedEncoder {
+
+    /// Fit encoder of given kind on data.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - Kind of encoder to fit.
+    /// * `data` - 2D array with one column per feature.
+    ///
+    pub fn fit(kind: EncoderKind, data: &ArrayView2<f64>) -> Self {
+        match kind {
+            EncoderKind::None => FittedEncoder::None,
+            EncoderKind::Standard => {
+                let mut scalar = StandardScalar::new();
+                scalar.transform(data);
+                FittedEncoder::Standard {
+                    mean: scalar.mean(),
+                    stdev: scalar.stdev()
+                }
+            },
+            EncoderKind::MinMax => {
+                let mut scalar = MinMax::new();
+                scalar.transform(data);
+                FittedEncoder::MinMax {
+                    min: scalar.min_range().clone(),
+                    max: scalar.max_range().clone()
+                }
+            },
+            EncoderKind::OneHot => {
+                let max_label = data.iter().fold(0.0_f64, |acc, &v| acc.max(v));
+                FittedEncoder::OneHot {
+                    num_classes: max_label as usize + 1
+                }
+            }
+        }
+    }
+
+    /// Number of columns the encoder expects, `None` if any width is accepted
+    pub fn width(&self) -> Option<usize> {
+        match self {
+            FittedEncoder::None => None,
+            FittedEncoder::Standard { mean, .. } => Some(mean.len()),
+            FittedEncoder::MinMax { min, .. } => Some(min.len()),
+            FittedEncoder::OneHot { .. } => Some(1)
+        }
+    }
+
+    /// Apply fitted encoder to data without refitting.
+    ///
+    /// Constant columns (zero deviation or range) are centered but not scaled.
+    pub fn transform(&self, data: &ArrayView2<f64>) -> PipelineResult<Array2<f64>> {
+
+        self.check_width(data)?;
+
+        let encoded = match self {
+            FittedEncoder::None => data.to_owned(),
+            FittedEncoder::Standard { mean, stdev } => {
+                let mut encoded = data.to_owned();
+                for (idx, mut col) in encoded.axis_iter_mut(Axis(1)).enumerate() {
+                    let scale = if stdev[idx] == 0.0 { 1.0 } else { stdev[idx] };
+                    col.mapv_inplace(|v| (v - mean[idx]) / scale);
+                }
+                encoded
+            },
+            FittedEncoder::MinMax { min, max } => {
+                let mut encoded = data.to_owned();
+                for (idx, mut col) in encoded.axis_iter_mut(Axis(1)).enumerate() {
+                    let range = max[idx] - min[idx];
+                    let range = if range == 0.0 { 1.0 } else { range };
+                    col.mapv_inplace(|v| (v - min[idx]) / range);
+                }
+                encoded
+            },
+            FittedEncoder::OneHot { num_classes } => {
+                let mut encoded = Array2::zeros((data.nrows(), *num_classes));
+                for (idx, &label) in data.iter().enumerate() {
+                    let class = label as usize;
+                    if label < 0.0 || label.fract() != 0.0 || class >= *num_classes {
+                        return Err(PipelineError::ShapeMismatch {
+                            context: "one hot encoding".to_string(),
+                            expected: format!("integer labels in 0..{num_classes}"),
+                            found: format!("label {label}")
+                        });
+                    }
+                    encoded[[idx, class]] = 1.0;
+                }
+                encoded
+            }
+        };
+
+        Ok(encoded)
+    }
+
+    /// Map encoded data back to the original scale
+    pub fn inverse_transform(&self, data: &ArrayView2<f64>) -> PipelineResult<Array2<f64>> {
+
+        let decoded = match self {
+            FittedEncoder::None => data.to_owned(),
+            FittedEncoder::Standard { mean, stdev } => {
+                self.check_width(data)?;
+                let mut decoded = data.to_owned();
+                for (idx, mut col) in decoded.axis_iter_mut(Axis(1)).enumerate() {
+                    let scale = if stdev[idx] == 0.0 { 1.0 } else { stdev[idx] };
+                    col.mapv_inplace(|v| v * scale + mean[idx]);
+                }
+                decoded
+            },
+            FittedEncoder::MinMax { min, max } => {
+                self.check_width(data)?;
+                let mut decoded = data.to_owned();
+                for (idx, mut col) in decoded.axis_iter_mut(Axis(1)).enumerate() {
+                    let range = max[idx] - min[idx];
+                    let range = if range == 0.0 { 1.0 } else { range };
+                    col.mapv_inplace(|v| v * range + min[idx]);
+                }
+                decoded
+            },
+            FittedEncoder::OneHot { .. } => {
+                let mut decoded = Array2::zeros((data.nrows(), 1));
+                for (idx, row) in data.axis_iter(Axis(0)).enumerate() {
+                    decoded[[idx, 0]] = argmax(&row) as f64;
+                }
+                decoded
+            }
+        };
+
+        Ok(decoded)
+    }
+
+    /// Factor every column is divided by when encoded, 1 for columns that are not scaled.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Number of columns, used when the encoder accepts any width.
+    ///
+    pub fn scales(&self, width: usize) -> Vec<f64> {
+        let nonzero = |scale: f64| if scale == 0.0 { 1.0 } else { scale };
+        match self {
+            FittedEncoder::Standard { stdev, .. } => stdev.iter().map(|&stdev| nonzero(stdev)).collect(),
+            FittedEncoder::MinMax { min, max } => min.iter().zip(max).map(|(min, max)| nonzero(max - min)).collect(),
+            FittedEncoder::None | FittedEncoder::OneHot { .. } => vec![1.0; width]
+        }
+    }
+
+    /// Whether the encoder rescales continuous values (and so must be inverted on predictions)
+    pub fn is_scaler(&self) -> bool {
+        matches!(self, FittedEncoder::Standard { .. } | FittedEncoder::MinMax { .. })
+    }
+
+    fn check_width(&self, data: &ArrayView2<f64>) -> PipelineResult<()> {
+        match self.width() {
+            Some(width) if width != data.ncols() => Err(PipelineError::ShapeMismatch {
+                context: "fitted encoder".to_string(),
+                expected: format!("{width} columns"),
+                found: format!("{} columns", data.ncols())
+            }),
+            _ => Ok(())
+        }
+    }
+
+}
+
+
+/// Preprocessing state saved next to the parameters of a trained model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Preprocessing {
+
+    /// Feature columns in the order the model expects them
+    pub features: Vec<String>,
+
+    /// Target column the model predicts
+    pub target: String,
+
+    /// Fitted encoder for feature columns
+    pub x_encode: FittedEncoder,
+
+    /// Fitted encoder for target column
+    pub y_encode: FittedEncoder,
+
+    /// Standard deviation of every raw feature over the training rows,
+    /// empty for models saved before it was recorded
+    #[serde(default)]
+    pub feature_stdev: Vec<f64>,
+
+    /// Name of every target class code, empty when the dataset names no labels
+    #[serde(default)]
+    pub class_names: Vec<String>
+}
+
+
+impl Preprocessing {
+
+    /// Standard deviation of every column of raw feature rows
+    pub fn column_stdev(x: &ArrayView2<f64>) -> Vec<f64> {
+        match x.nrows() {
+            0 => vec![0.0; x.ncols()],
+            _ => x.std_axis(Axis(0), 0.0).to_vec()
+        }
+    }
+
+    /// Name of a predicted class, its code when the class has no name
+    pub fn class_name(&self, class: usize) -> String {
+        self.class_names.get(class).cloned().unwrap_or(class.to_string())
+    }
+
+    /// Save preprocessing state to model artifact directory
+    pub fn save(&self, dir: &str) -> PipelineResult<()> {
+        write_json(dir, PREPROCESSING_FILE, self)
+    }
+
+    /// Load preprocessing state from model artifact directory
+    pub fn load(dir: &str) -> PipelineResult<Self> {
+        read_json(dir, PREPROCESSING_FILE)
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use ndarray::array;
+
+    use super::*;
+
+    /// Columns on different scales, the second constant
+    fn data() -> Array2<f64> {
+        array![[0.1, 7.0], [0.2, 7.0], [0.7, 7.0], [1.3, 7.0]]
+    }
+
+    #[test]
+    fn scalers_invert_and_leave_constant_columns_unscaled() {
+        let data = data();
+        for kind in [EncoderKind::Standard, EncoderKind::MinMax] {
+            let encoder = FittedEncoder::fit(kind, &data.view());
+            let encoded = encoder.transform(&data.view()).unwrap();
+            assert!(encoded.column(1).iter().all(|&v| v == 0.0), "{kind:?}: {encoded}");
+
+            let decoded = encoder.inverse_transform(&encoded.view()).unwrap();
+            assert!(decoded.iter().zip(&data).all(|(a, b)| (a - b).abs() < 1e-12), "{kind:?}: {decoded}");
+        }
+
+        let minmax = FittedEncoder::fit(EncoderKind::MinMax, &data.view());
+        assert_eq!(minmax, FittedEncoder::MinMax { min: vec![0.1, 7.0], max: vec![1.3, 7.0] });
+        let encoded = minmax.transform(&array![[0.1, 7.0], [1.3, 7.0]].view()).unwrap();
+        assert_eq!(encoded.column(0).to_vec(), [0.0, 1.0]);
+
+        let err = minmax.transform(&array![[0.1]].view()).unwrap_err();
+        assert_eq!(err.kind(), "shape_mismatch");
+    }
+
+    #[test]
+    fn one_hot_encodes_integer_labels_only() {
+        let labels = array![[0.0], [2.0], [1.0]];
+        let encoder = FittedEncoder::fit(EncoderKind::OneHot, &labels.view());
+        assert_eq!(encoder, FittedEncoder::OneHot { num_classes: 3 });
+
+        let encoded = encoder.transform(&labels.view()).unwrap();
+        assert_eq!(encoded, array![[1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]]);
+        assert_eq!(encoder.inverse_transform(&encoded.view()).unwrap(), labels);
+
+        for label in [1.5, -1.0, 3.0, f64::NAN] {
+            match encoder.transform(&array![[label]].view()) {
+                Err(PipelineError::ShapeMismatch { expected, .. }) => assert_eq!(expected, "integer labels in 0..3"),
+                other => panic!("expected label {label} to be rejected, found {other:?}")
+            }
+        }
+    }
+
+    #[test]
+    fn saved_encoders_transform_exactly_as_at_training() {
+        let dir = std::env::temp_dir().join("crate_preprocessing_tests/saved");
+        let dir = dir.to_string_lossy();
+        let _ = std::fs::remove_dir_all(&*dir);
+        std::fs::create_dir_all(&*dir).unwrap();
+
+        let data = data();
+        let target = array![[0.3], [1.7], [2.9], [4.1]];
+        let preprocessing = Preprocessing {
+            features: vec!["a".to_string(), "b".to_string()],
+            target: "y".to_string(),
+            x_encode: FittedEncoder::fit(EncoderKind::Standard, &data.view()),
+            y_encode: FittedEncoder::fit(EncoderKind::MinMax, &target.view()),
+            feature_stdev: Preprocessing::column_stdev(&data.view()),
+            class_names: vec!["low".to_string(), "high".to_string()]
+        };
+        preprocessing.save(&dir).unwrap();
+
+        let loaded = Preprocessing::load(&dir).unwrap();
+        assert_eq!(loaded, preprocessing);
+
+        // Statistics that JSON parsing only reads back exactly with float_roundtrip
+        let tiny = Preprocessing {
+            x_encode: FittedEncoder::Standard { mean: vec![5.0242958677880805e-15, 0.1 + 0.2], stdev: vec![1.0 / 3.0, 6.942244193120797e-17] },
+            ..preprocessing.clone()
+        };
+        tiny.save(&dir).unwrap();
+        assert_eq!(Preprocessing::load(&dir).unwrap(), tiny);
+        preprocessing.save(&dir).unwrap();
+        assert_eq!(loaded.x_encode.transform(&data.view()).unwrap(), preprocessing.x_encode.transform(&data.view()).unwrap());
+        assert_eq!(loaded.y_encode.transform(&target.view()).unwrap(), preprocessing.y_encode.transform(&target.view()).unwrap());
+        assert_eq!((loaded.class_name(1), loaded.class_name(2)), ("high".to_string(), "2".to_string()));
+    }
+
+}
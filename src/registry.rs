@@ -0,0 +1,353 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use serde::{Serialize, Deserialize};
+
+use crate::artifact::{read_json, write_json};
+use crate::error::*;
+use crate::store::{timestamp, Manifest, VersionSpec};
+
+
+/// File the registry of a pipeline's artifact store is saved to
+pub const REGISTRY_FILE: &str = "registry.json";
+
+
+/// Lifecycle stage of a saved model version
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelStage {
+
+    /// Newly trained version that has not been reviewed
+    #[default]
+    Candidate,
+
+    /// Version being validated before it serves
+    Staging,
+
+    /// Version serving predictions
+    Production,
+
+    /// Version retired from serving
+    Archived
+}
+
+
+impl ModelStage {
+
+    /// Every stage in lifecycle order
+    pub const ALL: [ModelStage; 4] = [
+        ModelStage::Candidate,
+        ModelStage::Staging,
+        ModelStage::Production,
+        ModelStage::Archived
+    ];
+
+    /// Name of stage as used in configs and on the command line
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModelStage::Candidate => "candidate",
+            ModelStage::Staging => "staging",
+            ModelStage::Production => "production",
+            ModelStage::Archived => "archived"
+        }
+    }
+
+    /// Parse stage from its name
+    pub fn parse(value: &str) -> Option<Self> {
+        ModelStage::ALL.into_iter().find(|stage| stage.name() == value)
+    }
+
+    /// Whether only a single version may hold the stage at a time
+    pub fn is_exclusive(&self) -> bool {
+        matches!(self, ModelStage::Staging | ModelStage::Production)
+    }
+
+}
+
+
+impl fmt::Display for ModelStage {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+
+}
+
+
+/// Record of a version moving between stages
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Promotion {
+
+    /// Version number moved
+    pub version: u32,
+
+    /// Stage the version left
+    pub from: ModelStage,
+
+    /// Stage the version entered
+    pub to: ModelStage,
+
+    /// Person or process that made the change
+    pub by: String,
+
+    /// UTC time of the change
+    pub at: String,
+
+    /// Reason given for the change
+    pub reason: String
+}
+
+
+/// Stages of the versions in a pipeline's artifact store.
+///
+/// Versions missing from the registry are candidates. Staging and
+/// production each hold at most one version, so promoting into them
+/// archives the version that held the stage before.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModelRegistry {
+
+    /// Stage of every version that has left candidate
+    pub stages: BTreeMap<u32, ModelStage>,
+
+    /// Every stage change, oldest first
+    pub promotions: Vec<Promotion>
+}
+
+
+impl ModelRegistry {
+
+    /// Load registry of a store root, empty when none has been saved
+    pub fn load(root: &str) -> PipelineResult<Self> {
+        match Path::new(root).join(REGISTRY_FILE).is_file() {
+            true => read_json(root, REGISTRY_FILE),
+            false => Ok(ModelRegistry::default())
+        }
+    }
+
+    /// Save registry to a store root
+    pub fn save(&self, root: &str) -> PipelineResult<()> {
+        write_json(root, REGISTRY_FILE, self)
+    }
+
+    /// Stage of a version
+    pub fn stage(&self, version: u32) -> ModelStage {
+        self.stages.get(&version).copied().unwrap_or_default()
+    }
+
+    /// Newest of the saved versions in a stage
+    pub fn latest_in<'a>(&self, stage: ModelStage, versions: &'a [Manifest]) -> Option<&'a Manifest> {
+        versions.iter().rev().find(|manifest| self.stage(manifest.version) == stage)
+    }
+
+    /// Whether a version has served in production before
+    fn has_served(&self, version: u32) -> bool {
+        self.promotions
+            .iter()
+            .any(|promotion| promotion.version == version && promotion.to == ModelStage::Production)
+    }
+
+    /// Move a version to another stage.
+    ///
+    /// Versions move from candidate to staging and from staging to
+    /// production. Any stage can be archived, and archived versions can
+    /// return to staging, or straight to production when they served there
+    /// before (a rollback). A version displaced from staging or production
+    /// is archived with its own promotion record.
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - Version number to move.
+    /// * `to` - Stage to move the version to.
+    /// * `by` - Person or process making the change.
+    /// * `reason` - Why the version is moved.
+    ///
+    /// Returns the promotions recorded, displaced versions first.
+    pub fn promote(&mut self, version: u32, to: ModelStage, by: &str, reason: &str) -> PipelineResult<Vec<Promotion>> {
+
+        let from = self.stage(version);
+        let spec = VersionSpec::Pinned(version);
+        if reason.trim().is_empty() {
+            return Err(PipelineError::Registry(format!("a reason is required to move {spec} to {to}")));
+        }
+
+        let allowed = match (from, to) {
+            (ModelStage::Candidate, ModelStage::Staging) => true,
+            (ModelStage::Staging, ModelStage::Production) => true,
+            (ModelStage::Archived, ModelStage::Staging) => true,
+            (ModelStage::Archived, ModelStage::Production) => self.has_served(version),
+            (from, ModelStage::Archived) => from != ModelStage::Archived,
+            _ => false
+        };
+        if !allowed {
+            return Err(PipelineError::Registry(format!("{spec} cannot move from {from} to {to}")));
+        }
+
+        let at = timestamp();
+        let mut recorded = Vec::new();
+        if to.is_exclusive() {
+            let displaced: Vec<u32> = self.stages
+                .iter()
+                .filter(|(_, stage)| **stage == to)
+                .map(|(displaced, _)| *displaced)
+                .collect();
+            for displaced in displaced {
+                recorded.push(Promotion {
+                    version: displaced,
+                    from: to,
+                    to: ModelStage::Archived,
+                    by: by.to_string(),
+                    at: at.clone(),
+                    reason: format!("replaced by {spec}")
+                });
+            }
+        }
+
+        recorded.push(Promotion {
+            version,
+            from,
+            to,
+            by: by.to_string(),
+            at,
+            reason: reason.trim().to_string()
+        });
+
+        for promotion in &recorded {
+            self.stages.insert(promotion.version, promotion.to);
+        }
+        self.promotions.extend(recorded.iter().cloned());
+        Ok(recorded)
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::config::ModelKind;
+
+    use ModelStage::*;
+
+    fn promote(registry: &mut ModelRegistry, version: u32, to: ModelStage) -> PipelineResult<Vec<Promotion>> {
+        registry.promote(version, to, "tests", "checked")
+    }
+
+    /// Registry with v1 in production and v2 in staging
+    fn serving() -> ModelRegistry {
+        let mut registry = ModelRegistry::default();
+        for (version, to) in [(1, Staging), (1, Production), (2, Staging)] {
+            promote(&mut registry, version, to).unwrap();
+        }
+        registry
+    }
+
+    fn manifest(version: u32) -> Manifest {
+        Manifest {
+            pipeline: "registry".to_string(),
+            version,
+            created_at: timestamp(),
+            model: ModelKind::Sgd,
+            seed: 42,
+            files: Vec::new(),
+            schema_version: 1,
+            checksums: BTreeMap::new(),
+            source: None
+        }
+    }
+
+    #[test]
+    fn promotes_candidates_through_staging() {
+        let mut registry = ModelRegistry::default();
+        assert_eq!(registry.stage(1), Candidate);
+
+        let recorded = registry.promote(1, Staging, "ana", "  passed review  ").unwrap();
+        assert_eq!(recorded.len(), 1);
+        let promotion = &recorded[0];
+        assert_eq!((promotion.version, promotion.from, promotion.to), (1, Candidate, Staging));
+        assert_eq!((promotion.by.as_str(), promotion.reason.as_str()), ("ana", "passed review"));
+
+        promote(&mut registry, 1, Production).unwrap();
+        assert_eq!(registry.stage(1), Production);
+        assert_eq!(registry.promotions.len(), 2);
+    }
+
+    #[test]
+    fn rejects_invalid_transitions() {
+        let mut registry = serving();
+        promote(&mut registry, 3, Archived).unwrap();
+        let before = registry.clone();
+
+        for (version, to) in [
+            (4, Production),
+            (4, Candidate),
+            (2, Candidate),
+            (1, Staging),
+            (1, Production),
+            (3, Archived),
+            (3, Production)
+        ] {
+            let err = promote(&mut registry, version, to).unwrap_err();
+            assert_eq!(err.kind(), "registry", "v{version} to {to}");
+        }
+        assert_eq!(registry.promote(4, Staging, "tests", " ").unwrap_err().kind(), "registry");
+        assert_eq!(registry, before);
+    }
+
+    #[test]
+    fn exclusive_stages_archive_the_displaced_version() {
+        let mut registry = serving();
+        let recorded = promote(&mut registry, 2, Production).unwrap();
+
+        assert_eq!(recorded.len(), 2);
+        assert_eq!((recorded[0].version, recorded[0].from, recorded[0].to), (1, Production, Archived));
+        assert_eq!(recorded[0].reason, "replaced by v2");
+        assert_eq!((recorded[1].version, recorded[1].from, recorded[1].to), (2, Staging, Production));
+        assert_eq!((registry.stage(1), registry.stage(2)), (Archived, Production));
+        assert_eq!(&registry.promotions[registry.promotions.len() - 2..], &recorded[..]);
+
+        promote(&mut registry, 3, Staging).unwrap();
+        let recorded = promote(&mut registry, 4, Staging).unwrap();
+        assert_eq!((recorded[0].version, recorded[0].to), (3, Archived));
+        assert_eq!(ModelStage::ALL.iter().filter(|stage| stage.is_exclusive()).count(), 2);
+    }
+
+    #[test]
+    fn rolls_back_to_versions_that_served() {
+        let mut registry = serving();
+        promote(&mut registry, 2, Production).unwrap();
+
+        // v1 served before it was displaced, v3 never did
+        let recorded = promote(&mut registry, 1, Production).unwrap();
+        assert_eq!((recorded[0].version, recorded[0].to), (2, Archived));
+        assert_eq!(registry.stage(1), Production);
+
+        promote(&mut registry, 3, Archived).unwrap();
+        assert!(promote(&mut registry, 3, Production).is_err());
+        promote(&mut registry, 3, Staging).unwrap();
+    }
+
+    #[test]
+    fn finds_newest_version_in_stage() {
+        let registry = serving();
+        let versions: Vec<Manifest> = (1..=4).map(manifest).collect();
+        assert_eq!(registry.latest_in(Production, &versions).map(|manifest| manifest.version), Some(1));
+        assert_eq!(registry.latest_in(Candidate, &versions).map(|manifest| manifest.version), Some(4));
+        assert!(registry.latest_in(Archived, &versions).is_none());
+    }
+
+    #[test]
+    fn saves_and_loads_registry() {
+        let root = std::env::temp_dir().join("crate_registry_tests");
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        let root = root.to_string_lossy();
+
+        assert_eq!(ModelRegistry::load(&root).unwrap(), ModelRegistry::default());
+        let registry = serving();
+        registry.save(&root).unwrap();
+        assert_eq!(ModelRegistry::load(&root).unwrap(), registry);
+    }
+
+}
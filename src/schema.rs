@@ -0,0 +1,567 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::artifact::{read_json, write_json, RunMetadata, METADATA_FILE};
+use crate::config::*;
+use crate::error::*;
+use crate::export::{ExportedModel, EXPORT_FILE};
+use crate::preprocessing::{FittedEncoder, Preprocessing, PREPROCESSING_FILE};
+use crate::store::*;
+use crate::tabular;
+
+
+/// Version of the artifact schema written by this release.
+///
+/// * `0` - flat directory saved by dendritic directly, without a manifest.
+///   Older runs recorded `graph_path` relative to other working directories
+///   and ridge models nest their SGD parameters under `sgd`.
+/// * `1` - store version with a manifest listing its files.
+/// * `2` - manifest records the schema version and a checksum of every file,
+///   and the version holds a compact `model.json` export.
+pub const SCHEMA_VERSION: u32 = 2;
+
+
+/// SHA-256 of a file as lowercase hex
+pub fn checksum(path: &str) -> PipelineResult<String> {
+    let bytes = fs::read(path).map_err(|err| PipelineError::artifact(path, err))?;
+    Ok(Sha256::digest(&bytes).iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+
+/// How serious a validation issue is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+
+    /// Version loads, but should be migrated
+    Warning,
+
+    /// Version cannot be trusted to load or predict correctly
+    Error
+}
+
+
+/// Problem found in a saved model version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Issue {
+
+    /// How serious the problem is
+    pub severity: Severity,
+
+    /// File relative to the version directory
+    pub file: String,
+
+    /// Description of the problem
+    pub problem: String
+}
+
+
+impl fmt::Display for Issue {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error"
+        };
+        write!(f, "{severity}: {}: {}", self.file, self.problem)
+    }
+
+}
+
+
+/// Issues found validating a saved model version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ValidationReport {
+
+    /// Version directory validated
+    pub dir: String,
+
+    /// Problems found, in the order they were checked
+    pub issues: Vec<Issue>
+}
+
+
+impl ValidationReport {
+
+    /// Whether no issue stops the version from being used
+    pub fn is_valid(&self) -> bool {
+        self.issues.iter().all(|issue| issue.severity == Severity::Warning)
+    }
+
+    /// Report when valid, otherwise an error naming every blocking issue
+    pub fn into_result(self) -> PipelineResult<Self> {
+        if self.is_valid() {
+            return Ok(self);
+        }
+        let errors: Vec<String> = self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+            .map(|issue| format!("{}: {}", issue.file, issue.problem))
+            .collect();
+        Err(PipelineError::InvalidArtifact { path: self.dir, message: errors.join("; ") })
+    }
+
+    fn error(&mut self, file: &str, problem: String) {
+        self.issues.push(Issue { severity: Severity::Error, file: file.to_string(), problem });
+    }
+
+    fn warning(&mut self, file: &str, problem: String) {
+        self.issues.push(Issue { severity: Severity::Warning, file: file.to_string(), problem });
+    }
+
+}
+
+
+/// Validate every file of a saved model version.
+///
+/// Checks the manifest's schema version and checksums, that the parameters
+/// hold exactly the fields dendritic expects for the model kind, that the
+/// graph files `graph_path` names exist with weights of `weight_dim`, and
+/// that the preprocessing and export line up with the weights.
+pub fn validate(dir: &str) -> ValidationReport {
+
+    let mut report = ValidationReport { dir: dir.to_string(), issues: Vec::new() };
+    let kind = check_manifest(dir, &mut report);
+    check_version(dir, kind, &mut report);
+    report
+}
+
+
+/// Validate only the parameters and graph a model is loaded from.
+///
+/// Run before handing a directory to dendritic, which panics on a missing
+/// graph and does not check that the graph matches the parameters.
+pub fn validate_graph(dir: &str) -> ValidationReport {
+    let mut report = ValidationReport { dir: dir.to_string(), issues: Vec::new() };
+    check_parameters(dir, None, &mut report);
+    report
+}
+
+
+/// Check manifest, returning the model kind it records
+fn check_manifest(dir: &str, report: &mut ValidationReport) -> Option<ModelKind> {
+
+    let Some(value) = read_value::<Manifest>(dir, MANIFEST_FILE, report) else {
+        report.error(MANIFEST_FILE, "missing, the directory is not a saved version (run migrate)".to_string());
+        return None;
+    };
+    let manifest: Manifest = serde_json::from_value(value).ok()?;
+
+    if manifest.schema_version < SCHEMA_VERSION {
+        report.warning(MANIFEST_FILE, format!(
+            "written with schema v{}, older than v{SCHEMA_VERSION} (run migrate)", manifest.schema_version
+        ));
+    } else if manifest.schema_version > SCHEMA_VERSION {
+        report.error(MANIFEST_FILE, format!(
+            "written with schema v{}, newer than the supported v{SCHEMA_VERSION}", manifest.schema_version
+        ));
+    }
+
+    for file in &manifest.files {
+        let path = format!("{dir}/{file}");
+        if !Path::new(&path).is_file() {
+            report.error(file, "listed in manifest but missing".to_string());
+            continue;
+        }
+        match (manifest.checksums.get(file), checksum(&path)) {
+            (Some(expected), Ok(found)) if *expected != found => {
+                report.error(file, "checksum does not match manifest, the file changed after it was saved".to_string());
+            },
+            (None, _) if manifest.schema_version >= SCHEMA_VERSION => {
+                report.error(file, "no checksum in manifest".to_string());
+            },
+            (_, Err(err)) => report.error(file, err.to_string()),
+            _ => {}
+        }
+    }
+
+    // Unlisted graph files can be loaded in place of the saved graph
+    if let Ok(entries) = fs::read_dir(format!("{dir}/{GRAPH_DIR}")) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let file = format!("{GRAPH_DIR}/{}", entry.file_name().to_string_lossy());
+            if !manifest.files.contains(&file) {
+                report.error(&file, "not listed in manifest and may shadow the saved graph (run prune)".to_string());
+            }
+        }
+    }
+
+    Some(manifest.model)
+}
+
+
+/// Check parameters, graph, preprocessing, export and run metadata
+fn check_version(dir: &str, kind: Option<ModelKind>, report: &mut ValidationReport) {
+
+    let Some((weight_dim, bias_dim)) = check_parameters(dir, kind, report) else { return };
+
+    match read_value::<Preprocessing>(dir, PREPROCESSING_FILE, report).map(serde_json::from_value::<Preprocessing>) {
+        Some(Ok(preprocessing)) if preprocessing.features.len() != weight_dim.0 => {
+            report.error(PREPROCESSING_FILE, format!(
+                "{} features for {} weight rows", preprocessing.features.len(), weight_dim.0
+            ));
+        },
+        Some(_) => {},
+        None => report.error(PREPROCESSING_FILE, "missing, raw rows cannot be encoded".to_string())
+    }
+
+    match read_value::<ExportedModel>(dir, EXPORT_FILE, report).map(serde_json::from_value::<ExportedModel>) {
+        Some(Ok(exported)) => {
+            let rows = exported.weights.len();
+            let cols = exported.weights.first().map_or(0, |row| row.len());
+            if (rows, cols) != weight_dim || exported.bias.len() != bias_dim.1 {
+                report.error(EXPORT_FILE, format!(
+                    "weights are {:?} with {} bias values, parameters have {weight_dim:?} and {bias_dim:?}",
+                    (rows, cols),
+                    exported.bias.len()
+                ));
+            }
+        },
+        Some(Err(_)) => {},
+        None => report.warning(EXPORT_FILE, "missing compact export (run export or migrate)".to_string())
+    }
+
+    read_value::<RunMetadata>(dir, METADATA_FILE, report);
+}
+
+
+/// Fields dendritic saves to the parameters of a model kind
+fn parameter_fields(kind: ModelKind) -> &'static [&'static str] {
+    match kind {
+        ModelKind::Sgd => &["graph_path", "weight_dim", "bias_dim", "learning_rate"],
+        ModelKind::Logistic => &["graph_path", "weight_dim", "bias_dim", "learning_rate", "multi_class"]
+    }
+}
+
+
+/// Check parameters and the graph they point to, returning weight and bias dimensions
+fn check_parameters(
+    dir: &str,
+    kind: Option<ModelKind>,
+    report: &mut ValidationReport) -> Option<((usize, usize), (usize, usize))> {
+
+    let Ok(Value::Object(parameters)) = read_json::<Value>(dir, PARAMETERS_FILE) else {
+        report.error(PARAMETERS_FILE, "missing or not a JSON object".to_string());
+        return None;
+    };
+
+    if parameters.contains_key("sgd") {
+        report.error(PARAMETERS_FILE, "nested ridge parameters from an older dendritic (run migrate)".to_string());
+        return None;
+    }
+
+    let kind = kind.unwrap_or(match parameters.contains_key("multi_class") {
+        true => ModelKind::Logistic,
+        false => ModelKind::Sgd
+    });
+    let fields = parameter_fields(kind);
+    for field in fields {
+        if !parameters.contains_key(*field) {
+            report.error(PARAMETERS_FILE, format!("missing field {field:?} for {kind:?} models"));
+        }
+    }
+    for field in parameters.keys().filter(|field| !fields.contains(&field.as_str())) {
+        report.error(PARAMETERS_FILE, format!("unknown field {field:?} for {kind:?} models"));
+    }
+
+    let dim = |field: &str| serde_json::from_value::<(usize, usize)>(parameters.get(field)?.clone()).ok();
+    let (Some(weight_dim), Some(bias_dim)) = (dim("weight_dim"), dim("bias_dim")) else {
+        report.error(PARAMETERS_FILE, "weight_dim and bias_dim must be pairs of sizes".to_string());
+        return None;
+    };
+    let graph_path = parameters.get("graph_path").and_then(|path| path.as_str())?;
+
+    let expected = format!("{dir}/{GRAPH_DIR}");
+    if graph_path != expected {
+        report.error(PARAMETERS_FILE, format!("graph_path {graph_path:?} does not point at {expected:?} (run migrate)"));
+    }
+
+    let prefix = graph_prefix(graph_path);
+    if !Path::new(&format!("{graph_path}/{prefix}_metadata.json")).is_file() {
+        report.error(GRAPH_DIR, format!("missing {prefix}_metadata.json for graph_path {graph_path:?}"));
+    }
+
+    let nodes_file = format!("{prefix}_nodes.json");
+    match read_json::<Value>(graph_path, &nodes_file) {
+        Ok(nodes) => {
+            // dendritic keeps the weights in node 1 and the bias in node 3
+            let node_dim = |idx: usize| serde_json::from_value::<(usize, usize)>(nodes[idx]["value"]["value"]["dim"].clone()).ok();
+            for (name, idx, dim) in [("weights", 1, weight_dim), ("bias", 3, bias_dim)] {
+                match node_dim(idx) {
+                    Some(found) if found == dim => {},
+                    found => report.error(&format!("{GRAPH_DIR}/{nodes_file}"), format!(
+                        "{name} node is {}, parameters expect {dim:?}",
+                        found.map_or("missing".to_string(), |found| format!("{found:?}"))
+                    ))
+                }
+            }
+        },
+        Err(err) => report.error(&format!("{GRAPH_DIR}/{nodes_file}"), err.to_string())
+    }
+
+    Some((weight_dim, bias_dim))
+}
+
+
+/// Read a JSON document of a version, reporting fields its type does not know.
+///
+/// Returns `None` without an issue when the file does not exist.
+fn read_value<T: Serialize + DeserializeOwned>(dir: &str, file: &str, report: &mut ValidationReport) -> Option<Value> {
+
+    if !Path::new(dir).join(file).is_file() {
+        return None;
+    }
+
+    let value: Value = match read_json(dir, file) {
+        Ok(value) => value,
+        Err(err) => {
+            report.error(file, err.to_string());
+            return Some(Value::Null);
+        }
+    };
+
+    // Fields dropped by a round trip through the type are unknown to it
+    let known = match serde_json::from_value::<T>(value.clone()).map(|parsed| serde_json::to_value(parsed)) {
+        Ok(Ok(known)) => known,
+        Ok(Err(err)) | Err(err) => {
+            report.error(file, format!("does not match the schema: {err}"));
+            return Some(Value::Null);
+        }
+    };
+
+    if let (Value::Object(fields), Value::Object(known)) = (&value, &known) {
+        for field in fields.keys().filter(|field| !known.contains_key(*field)) {
+            report.error(file, format!("unknown field {field:?}"));
+        }
+    }
+    Some(value)
+}
+
+
+/// Upgrade the saved versions of a pipeline to the current schema.
+///
+/// Versions written with an older schema get a compact export and a
+/// checksummed manifest. A legacy flat artifact directory, `legacy` or the
+/// store root when dendritic saved straight into it, is imported as a new
+/// version.
+///
+/// # Arguments
+///
+/// * `config` - Experiment whose artifact store is migrated.
+/// * `legacy` - Flat artifact directory to import, the store root when `None`.
+///
+/// Returns a note for every change made.
+pub fn migrate(config: &ExperimentConfig, legacy: Option<&str>) -> PipelineResult<Vec<String>> {
+
+    let store = ArtifactStore::new(&config.output_dir);
+    let mut notes = Vec::new();
+
+    let versions = store.versions()?;
+    for mut manifest in versions.iter().filter(|manifest| manifest.schema_version < SCHEMA_VERSION).cloned() {
+        let dir = store.version_dir(manifest.version);
+        let from = manifest.schema_version;
+        if !Path::new(&dir).join(EXPORT_FILE).is_file() && manifest.model == config.model.kind {
+            tabular::export_version(&dir, manifest.model, config.task())?.save(&dir)?;
+        }
+        store.seal(&mut manifest)?;
+        notes.push(format!("{}: schema v{from} -> v{SCHEMA_VERSION}", manifest.name()));
+    }
+
+    let root_legacy = Path::new(store.root()).join(PARAMETERS_FILE).is_file();
+    let source = match legacy {
+        Some(source) => source.trim_end_matches('/'),
+        None if root_legacy => store.root(),
+        None => return Ok(notes)
+    };
+
+    match versions.iter().find(|manifest| manifest.source.as_deref() == Some(source)) {
+        Some(manifest) => notes.push(format!("{source}: already imported as {}", manifest.name())),
+        None => notes.push(import_legacy(&store, config, source)?)
+    }
+    Ok(notes)
+}
+
+
+/// Import a flat artifact directory saved by dendritic as a new version
+fn import_legacy(store: &ArtifactStore, config: &ExperimentConfig, source: &str) -> PipelineResult<String> {
+
+    let parameter_path = format!("{source}/{PARAMETERS_FILE}");
+    let mut parameters: Value = read_json(source, PARAMETERS_FILE)?;
+    let mut notes = Vec::new();
+
+    // Ridge models nest the SGD parameters next to their penalty
+    if let Some(inner) = parameters.get("sgd").cloned() {
+        notes.push(format!("dropped ridge penalty lambda = {}", parameters["lambda"]));
+        parameters = inner;
+    }
+
+    let Value::Object(fields) = &mut parameters else {
+        return Err(PipelineError::InvalidArtifact { path: parameter_path, message: "not a JSON object".to_string() });
+    };
+    let (weight_dim, graph_path) = match (fields.get("weight_dim"), fields.get("graph_path").and_then(|path| path.as_str())) {
+        (Some(dim), Some(graph_path)) => match serde_json::from_value::<(usize, usize)>(dim.clone()) {
+            Ok(dim) => (dim, graph_path.to_string()),
+            Err(err) => return Err(PipelineError::InvalidArtifact { path: parameter_path, message: err.to_string() })
+        },
+        _ => return Err(PipelineError::InvalidArtifact {
+            path: parameter_path,
+            message: "missing weight_dim or graph_path".to_string()
+        })
+    };
+
+    let features = config.dataset.features.len();
+    if weight_dim.0 != features {
+        return Err(PipelineError::ShapeMismatch {
+            context: format!("{source} legacy weights"),
+            expected: format!("{features} feature rows for {}", config.name),
+            found: format!("{weight_dim:?}")
+        });
+    }
+
+    match (config.model.kind, fields.get("multi_class").and_then(|multi| multi.as_bool())) {
+        (ModelKind::Sgd, None) => {},
+        (ModelKind::Logistic, None) => {
+            fields.insert("multi_class".to_string(), Value::Bool(config.model.multi_class));
+            notes.push(format!("added multi_class = {}", config.model.multi_class));
+        },
+        (ModelKind::Logistic, Some(multi_class)) if multi_class == config.model.multi_class => {},
+        (kind, _) => return Err(PipelineError::Config(format!(
+            "{source} does not hold a {kind:?} model with multi_class = {} as {} trains",
+            config.model.multi_class,
+            config.name
+        )))
+    }
+
+    // Legacy runs saved no encoders, so a model trained on scaled features
+    // or targets cannot be rebuilt and would predict on the wrong scale
+    let scaled: Vec<&str> = [("features", config.transforms.features), ("target", config.transforms.target)]
+        .into_iter()
+        .filter(|(_, kind)| matches!(kind, EncoderKind::Standard | EncoderKind::MinMax))
+        .map(|(column, _)| column)
+        .collect();
+    if !scaled.is_empty() {
+        return Err(PipelineError::Config(format!(
+            "{source} cannot be imported into {}: it scales its {} with encoders legacy artifacts never saved, retrain instead",
+            config.name,
+            scaled.join(" and ")
+        )));
+    }
+
+    // Graph files are named after the path they were saved to, which older
+    // runs recorded relative to other working directories
+    let graph_dir = format!("{source}/{GRAPH_DIR}");
+    let prefix = legacy_graph_prefix(&graph_dir, &graph_path)?;
+
+    let (version, dir) = store.create()?;
+    let new_graph_path = format!("{dir}/{GRAPH_DIR}");
+    let new_prefix = graph_prefix(&new_graph_path);
+    fs::create_dir_all(&new_graph_path).map_err(|err| PipelineError::artifact(&new_graph_path, err))?;
+    for suffix in ["nodes", "metadata"] {
+        let from = format!("{graph_dir}/{prefix}_{suffix}.json");
+        let to = format!("{new_graph_path}/{new_prefix}_{suffix}.json");
+        fs::copy(&from, &to).map_err(|err| PipelineError::artifact(&from, err))?;
+    }
+    fields.insert("graph_path".to_string(), Value::String(new_graph_path));
+    write_json(&dir, PARAMETERS_FILE, &parameters)?;
+
+    let preprocessing = Preprocessing {
+        features: config.dataset.features.clone(),
+        target: config.dataset.target.clone(),
+        x_encode: FittedEncoder::None,
+        y_encode: match config.transforms.target {
+            EncoderKind::OneHot => FittedEncoder::OneHot { num_classes: weight_dim.1 },
+            _ => FittedEncoder::None
+        },
+        feature_stdev: Vec::new(),
+        class_names: Vec::new()
+    };
+    preprocessing.save(&dir)?;
+    tabular::export_version(&dir, config.model.kind, config.task())?.save(&dir)?;
+
+    let mut manifest = Manifest {
+        pipeline: config.name.clone(),
+        version,
+        created_at: timestamp(),
+        model: config.model.kind,
+        seed: config.seed,
+        files: Vec::new(),
+        schema_version: SCHEMA_VERSION,
+        checksums: Default::default(),
+        source: Some(source.to_string())
+    };
+    store.seal(&mut manifest)?;
+
+    let mut note = format!("{source}: imported as {}", manifest.name());
+    if !notes.is_empty() {
+        note = format!("{note} ({})", notes.join("; "));
+    }
+    Ok(note)
+}
+
+
+/// Prefix of the graph a legacy directory's parameters were saved with.
+///
+/// Falls back to the only graph in the directory when none matches.
+fn legacy_graph_prefix(graph_dir: &str, graph_path: &str) -> PipelineResult<String> {
+
+    let expected = graph_prefix(graph_path).to_string();
+    let prefixes: Vec<String> = fs::read_dir(graph_dir)
+        .map_err(|err| PipelineError::artifact(graph_dir, err))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().to_string_lossy().strip_suffix("_nodes.json").map(str::to_string))
+        .collect();
+
+    match prefixes.contains(&expected) {
+        true => Ok(expected),
+        false if prefixes.len() == 1 => Ok(prefixes[0].clone()),
+        false => Err(PipelineError::InvalidArtifact {
+            path: graph_dir.to_string(),
+            message: format!("{} graphs and none saved to {graph_path:?}", prefixes.len())
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Titanic config whose artifact store is a fresh temp directory
+    fn titanic(name: &str, features: EncoderKind) -> ExperimentConfig {
+        let mut config = crate::titanic::config();
+        config.output_dir = std::env::temp_dir().join("crate_schema_tests").join(name).to_string_lossy().into_owned();
+        config.transforms.features = features;
+        let _ = fs::remove_dir_all(&config.output_dir);
+        config
+    }
+
+    #[test]
+    fn legacy_import_refuses_missing_encoders() {
+        let config = titanic("scaled", EncoderKind::Standard);
+        match migrate(&config, Some("models/titanic")) {
+            Err(PipelineError::Config(message)) => assert!(message.contains("features"), "{message}"),
+            other => panic!("expected a config error, found {other:?}")
+        }
+        assert!(ArtifactStore::new(&config.output_dir).versions().unwrap().is_empty());
+    }
+
+    #[test]
+    fn legacy_import_validates() {
+        let config = titanic("raw", EncoderKind::None);
+        let notes = migrate(&config, Some("models/titanic")).unwrap();
+        assert!(notes[0].contains("imported as v1"), "{notes:?}");
+
+        let dir = ArtifactStore::new(&config.output_dir).dir(VersionSpec::Latest).unwrap();
+        assert!(validate(&dir).is_valid());
+
+        // A second run finds the import by its source
+        assert!(migrate(&config, Some("models/titanic")).unwrap()[0].contains("already imported"));
+    }
+
+}
@@ -0,0 +1,367 @@
+use std::fmt::Write;
+use std::fs;
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::{Serialize, Deserialize};
+
+use crate::artifact::{read_json, write_json};
+use crate::config::*;
+use crate::error::*;
+use crate::metrics::lower_is_better;
+
+
+/// File search results are saved to
+pub const SEARCH_FILE: &str = "search.json";
+
+/// File the Markdown search results table is saved to
+pub const SEARCH_SUMMARY_FILE: &str = "search.md";
+
+
+/// Hyperparameters of a single search candidate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candidate {
+
+    /// Learning rate of model
+    pub learning_rate: f64,
+
+    /// Number of rows in each batch
+    pub batch_size: usize,
+
+    /// Number of training iterations
+    pub iterations: usize,
+
+    /// Number of epochs trained within each iteration
+    pub batch_epochs: usize,
+
+    /// Optimizer used for parameter updates
+    pub optimizer: OptimizerKind
+}
+
+
+impl Candidate {
+
+    /// Experiment config with the candidate's hyperparameters applied
+    pub fn apply(&self, config: &ExperimentConfig) -> ExperimentConfig {
+        let mut config = config.clone();
+        config.model.learning_rate = self.learning_rate;
+        config.model.optimizer = self.optimizer;
+        config.hyperparameters.batch_size = self.batch_size;
+        config.hyperparameters.iterations = self.iterations;
+        config.hyperparameters.batch_epochs = self.batch_epochs;
+        config
+    }
+
+}
+
+
+/// Draw candidates from the search space of an experiment config.
+///
+/// Grid search returns every combination of the listed values, random search
+/// a sample of `trials` distinct combinations. Settings without listed values
+/// keep the value of the experiment config.
+///
+/// # Arguments
+///
+/// * `config` - Experiment config holding the search space.
+/// * `rng` - Random source used to sample random search candidates.
+///
+pub fn candidates<R: Rng + ?Sized>(config: &ExperimentConfig, rng: &mut R) -> Vec<Candidate> {
+
+    let (search, model, params) = (&config.search, &config.model, &config.hyperparameters);
+    let learning_rates = or_base(&search.learning_rate, model.learning_rate);
+    let optimizers = or_base(&search.optimizer, model.optimizer);
+    let batch_sizes = or_base(&search.batch_size, params.batch_size);
+    let iterations = or_base(&search.iterations, params.iterations);
+    let batch_epochs = or_base(&search.batch_epochs, params.batch_epochs);
+
+    let mut grid = Vec::new();
+    for &learning_rate in &learning_rates {
+        for &optimizer in &optimizers {
+            for &batch_size in &batch_sizes {
+                for &iterations in &iterations {
+                    for &batch_epochs in &batch_epochs {
+                        grid.push(Candidate { learning_rate, batch_size, iterations, batch_epochs, optimizer });
+                    }
+                }
+            }
+        }
+    }
+
+    if search.strategy == SearchStrategy::Random {
+        grid.shuffle(rng);
+        grid.truncate(search.trials);
+    }
+    grid
+}
+
+
+/// Listed values, or the config value when none are listed
+fn or_base<T: Clone>(values: &[T], base: T) -> Vec<T> {
+    match values.is_empty() {
+        true => vec![base],
+        false => values.to_vec()
+    }
+}
+
+
+/// Outcome of training and scoring one candidate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Trial {
+
+    /// Position in the ranking, starting at 1, none if the candidate failed
+    pub rank: Option<usize>,
+
+    /// Hyperparameters of candidate
+    pub candidate: Candidate,
+
+    /// Score of the ranking metric
+    pub score: Option<f64>,
+
+    /// Standard deviation of the score over folds (cross validation only)
+    pub std: Option<f64>,
+
+    /// Reason the candidate could not be scored
+    pub error: Option<String>
+}
+
+
+/// Ranked results of a hyperparameter search
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchResults {
+
+    /// Name of experiment searched
+    pub experiment: String,
+
+    /// How candidates were drawn
+    pub strategy: SearchStrategy,
+
+    /// How candidates were scored
+    pub selection: Selection,
+
+    /// Metric candidates were ranked by
+    pub metric: String,
+
+    /// Seed of the run
+    pub seed: u64,
+
+    /// Every candidate, best first
+    pub trials: Vec<Trial>
+}
+
+
+impl SearchResults {
+
+    /// Rank scored trials by metric, best first, with failed trials last.
+    ///
+    /// A NaN or infinite score, as from a diverged model, fails its trial.
+    pub fn rank(&mut self) {
+
+        for trial in &mut self.trials {
+            if let Some(score) = trial.score && !score.is_finite() {
+                trial.score = None;
+                trial.std = None;
+                trial.error.get_or_insert(format!("{} is {score}", self.metric));
+            }
+        }
+
+        let lower = lower_is_better(&self.metric);
+        self.trials.sort_by(|a, b| match (a.score, b.score) {
+            (Some(a), Some(b)) if lower => a.total_cmp(&b),
+            (Some(a), Some(b)) => b.total_cmp(&a),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal
+        });
+
+        let mut rank = 0;
+        for trial in &mut self.trials {
+            trial.rank = trial.score.map(|_| {
+                rank += 1;
+                rank
+            });
+        }
+    }
+
+    /// Best scoring trial
+    pub fn best(&self) -> Option<&Trial> {
+        self.trials.first().filter(|trial| trial.score.is_some())
+    }
+
+    /// Save results as JSON and a Markdown table to model artifact directory
+    pub fn save(&self, dir: &str) -> PipelineResult<()> {
+        write_json(dir, SEARCH_FILE, self)?;
+        let path = format!("{dir}/{SEARCH_SUMMARY_FILE}");
+        fs::write(&path, self.to_markdown()).map_err(|err| PipelineError::artifact(&path, err))
+    }
+
+    /// Load results from model artifact directory
+    pub fn load(dir: &str) -> PipelineResult<Self> {
+        read_json(dir, SEARCH_FILE)
+    }
+
+    /// Render results as a Markdown table
+    pub fn to_markdown(&self) -> String {
+
+        let mut md = String::new();
+        let _ = writeln!(md, "# Hyperparameter Search: {}\n", self.experiment);
+        let _ = writeln!(
+            md,
+            "Strategy: {:?} | Selection: {:?} | Metric: {} | Seed: {}\n",
+            self.strategy, self.selection, self.metric, self.seed
+        );
+
+        let _ = writeln!(md, "| Rank | Learning rate | Batch size | Iterations | Batch epochs | Optimizer | {} |", self.metric);
+        let _ = writeln!(md, "| --- | --- | --- | --- | --- | --- | --- |");
+        for trial in &self.trials {
+            let candidate = &trial.candidate;
+            let rank = trial.rank.map(|rank| rank.to_string()).unwrap_or("-".to_string());
+            let score = match (trial.score, trial.std, &trial.error) {
+                (Some(score), Some(std), _) => format!("{score:.4} ± {std:.4}"),
+                (Some(score), None, _) => format!("{score:.4}"),
+                (None, _, Some(error)) => format!("failed: {error}"),
+                (None, _, None) => "-".to_string()
+            };
+            let _ = writeln!(
+                md,
+                "| {rank} | {} | {} | {} | {} | {:?} | {score} |",
+                candidate.learning_rate,
+                candidate.batch_size,
+                candidate.iterations,
+                candidate.batch_epochs,
+                candidate.optimizer
+            );
+        }
+
+        md
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::*;
+
+    fn config() -> ExperimentConfig {
+        let mut config = crate::iris::config();
+        config.search.learning_rate = vec![0.1, 0.01, 0.001];
+        config.search.batch_size = vec![16, 32];
+        config.search.optimizer = vec![OptimizerKind::Default, OptimizerKind::Adam];
+        config
+    }
+
+    fn results(metric: &str, scores: &[Option<f64>]) -> SearchResults {
+        let base = config();
+        let trials = scores
+            .iter()
+            .enumerate()
+            .map(|(idx, &score)| Trial {
+                rank: None,
+                candidate: Candidate { learning_rate: idx as f64, ..candidates(&base, &mut StdRng::seed_from_u64(0))[0].clone() },
+                score,
+                std: None,
+                error: score.is_none().then(|| "diverged".to_string())
+            })
+            .collect();
+        SearchResults {
+            experiment: "search".to_string(),
+            strategy: SearchStrategy::Grid,
+            selection: Selection::Validation,
+            metric: metric.to_string(),
+            seed: 7,
+            trials
+        }
+    }
+
+    /// Learning rates of trials in ranked order, which identify the trial's input position
+    fn order(results: &SearchResults) -> Vec<usize> {
+        results.trials.iter().map(|trial| trial.candidate.learning_rate as usize).collect()
+    }
+
+    #[test]
+    fn grid_covers_every_combination() {
+        let config = config();
+        let grid = candidates(&config, &mut StdRng::seed_from_u64(0));
+        assert_eq!(grid.len(), 12);
+
+        // Settings without listed values keep the config's value
+        let params = &config.hyperparameters;
+        assert!(grid.iter().all(|candidate| candidate.iterations == params.iterations && candidate.batch_epochs == params.batch_epochs));
+        for (idx, candidate) in grid.iter().enumerate() {
+            assert!(!grid[..idx].contains(candidate));
+        }
+
+        let applied = grid[5].apply(&config);
+        assert_eq!((applied.model.learning_rate, applied.hyperparameters.batch_size), (grid[5].learning_rate, grid[5].batch_size));
+        assert_eq!(applied.model.optimizer, grid[5].optimizer);
+    }
+
+    #[test]
+    fn random_search_samples_distinct_candidates_by_seed() {
+        let mut config = config();
+        config.search.strategy = SearchStrategy::Random;
+        config.search.trials = 5;
+        let grid = candidates(&config, &mut StdRng::seed_from_u64(3));
+        assert_eq!(grid.len(), 5);
+        for (idx, candidate) in grid.iter().enumerate() {
+            assert!(!grid[..idx].contains(candidate));
+        }
+        assert_eq!(grid, candidates(&config, &mut StdRng::seed_from_u64(3)));
+
+        config.search.trials = 50;
+        assert_eq!(candidates(&config, &mut StdRng::seed_from_u64(3)).len(), 12);
+    }
+
+    #[test]
+    fn ranks_higher_scores_first() {
+        let mut search = results("accuracy", &[Some(0.7), Some(0.9), Some(0.8)]);
+        search.rank();
+        assert_eq!(order(&search), [1, 2, 0]);
+        assert_eq!(search.trials.iter().map(|trial| trial.rank).collect::<Vec<_>>(), [Some(1), Some(2), Some(3)]);
+        assert_eq!(search.best().unwrap().score, Some(0.9));
+    }
+
+    #[test]
+    fn ranks_lower_losses_first() {
+        for metric in ["rmse", "log_loss"] {
+            let mut search = results(metric, &[Some(0.7), Some(0.9), Some(0.2)]);
+            search.rank();
+            assert_eq!(order(&search), [2, 0, 1], "{metric}");
+        }
+    }
+
+    #[test]
+    fn ranks_failed_candidates_last_without_a_rank() {
+        let mut search = results("accuracy", &[None, Some(0.5), None, Some(0.6)]);
+        search.rank();
+        assert_eq!(order(&search), [3, 1, 0, 2]);
+        assert_eq!(search.trials.iter().map(|trial| trial.rank).collect::<Vec<_>>(), [Some(1), Some(2), None, None]);
+        let md = search.to_markdown();
+        assert!(md.contains("| - | 0 |") && md.contains("failed: diverged"), "{md}");
+
+        let mut failed = results("accuracy", &[None, None]);
+        failed.rank();
+        assert!(failed.best().is_none());
+    }
+
+    #[test]
+    fn ranks_non_finite_scores_as_failed() {
+        let mut search = results("r2", &[Some(f64::NAN), Some(0.4), Some(f64::INFINITY), Some(0.8)]);
+        search.rank();
+        assert_eq!(order(&search), [3, 1, 0, 2]);
+        assert_eq!(search.best().unwrap().score, Some(0.8));
+        assert_eq!(search.trials[2].error.as_deref(), Some("r2 is NaN"));
+        assert_eq!(search.trials[3].rank, None);
+
+        let mut search = results("rmse", &[Some(f64::NAN), Some(f64::NEG_INFINITY), Some(2.0)]);
+        search.rank();
+        assert_eq!(order(&search), [2, 0, 1]);
+        assert_eq!(search.trials.iter().filter(|trial| trial.score.is_some()).count(), 1);
+    }
+
+}
@@ -0,0 +1,495 @@
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+use ndarray::{Array2, Axis};
+use serde::{Serialize, Deserialize};
+use serde_json::{Map, Value};
+
+use crate::Task;
+use crate::config::{ExperimentConfig, ModelKind};
+use crate::error::*;
+use crate::export::ExportedModel;
+use crate::registry::ModelStage;
+use crate::store::{ArtifactStore, VersionSpec};
+use crate::tabular;
+
+
+/// Port the prediction server listens on by default
+pub const DEFAULT_PORT: u16 = 8080;
+
+/// Largest request body the server reads, in bytes
+const MAX_BODY_BYTES: usize = 16 * 1024 * 1024;
+
+
+/// Saved model version loaded for serving
+pub struct ServedModel {
+
+    /// Name of experiment the model is served under
+    pub name: String,
+
+    /// Version number served
+    pub version: u32,
+
+    /// Registry stage of the version when it was loaded
+    pub stage: ModelStage,
+
+    /// Weights, bias and preprocessing of the model
+    pub model: ExportedModel
+}
+
+
+impl ServedModel {
+
+    /// Load and validate the saved version an experiment config names
+    pub fn load(config: &ExperimentConfig) -> PipelineResult<Self> {
+        let (version, model) = tabular::load_export(config)?;
+        Ok(ServedModel {
+            name: config.name.clone(),
+            version,
+            stage: ArtifactStore::new(&config.output_dir).registry()?.stage(version),
+            model
+        })
+    }
+
+    /// Description of the model listed by `/v1/models`
+    pub fn info(&self) -> ModelInfo {
+        let preprocessing = &self.model.preprocessing;
+        ModelInfo {
+            name: self.name.clone(),
+            version: VersionSpec::Pinned(self.version).to_string(),
+            stage: self.stage,
+            model: self.model.model,
+            task: self.model.task,
+            target: preprocessing.target.clone(),
+            features: preprocessing.features.clone(),
+            classes: self.model.classes()
+        }
+    }
+
+    /// Predict on rows of named features.
+    ///
+    /// Every feature the model was trained on must be given as a number
+    /// (or boolean), and names the model does not know are rejected.
+    pub fn predict(&self, instances: &[Map<String, Value>]) -> Result<Vec<Prediction>, String> {
+
+        let features = self.model.features();
+        let mut x = Array2::zeros((instances.len(), features.len()));
+        for (row, instance) in instances.iter().enumerate() {
+            if let Some(unknown) = instance.keys().find(|name| !features.contains(name)) {
+                return Err(format!("instance {row} has unknown feature {unknown:?}"));
+            }
+            for (col, feature) in features.iter().enumerate() {
+                x[[row, col]] = match instance.get(feature) {
+                    Some(Value::Number(number)) => number.as_f64().unwrap_or(f64::NAN),
+                    Some(Value::Bool(flag)) => f64::from(u8::from(*flag)),
+                    Some(other) => return Err(format!("instance {row} feature {feature:?} is not a number: {other}")),
+                    None => return Err(format!("instance {row} is missing feature {feature:?}"))
+                };
+            }
+        }
+
+        if self.model.task == Task::Regression {
+            let predictions = self.model.predict(&x).map_err(|err| err.to_string())?;
+            return Ok(predictions
+                .column(0)
+                .iter()
+                .map(|&value| Prediction { prediction: value, label: None, probabilities: None })
+                .collect());
+        }
+
+        let names = self.model.classes();
+        let (classes, probabilities) = self.model.classify(&x).map_err(|err| err.to_string())?;
+        Ok(classes
+            .into_iter()
+            .zip(probabilities.axis_iter(Axis(0)))
+            .map(|(class, row)| Prediction {
+                prediction: class as f64,
+                label: Some(names[class].clone()),
+                probabilities: Some(names.iter().cloned().zip(row.iter().copied()).collect())
+            })
+            .collect())
+    }
+
+}
+
+
+/// Served model as listed by `/v1/models`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelInfo {
+
+    /// Name the model is served under
+    pub name: String,
+
+    /// Version served
+    pub version: String,
+
+    /// Registry stage of the version
+    pub stage: ModelStage,
+
+    /// Kind of model
+    pub model: ModelKind,
+
+    /// Prediction task of the model
+    pub task: Task,
+
+    /// Target column the model predicts
+    pub target: String,
+
+    /// Features every instance must name
+    pub features: Vec<String>,
+
+    /// Class labels in code order, empty for regression
+    pub classes: Vec<String>
+}
+
+
+/// Prediction for one instance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Prediction {
+
+    /// Predicted target, the class code for classifiers
+    pub prediction: f64,
+
+    /// Name of the predicted class
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+
+    /// Probability of every class keyed by class name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub probabilities: Option<BTreeMap<String, f64>>
+}
+
+
+/// Status code and JSON body of a response
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+
+    /// HTTP status code
+    pub status: u16,
+
+    /// JSON body
+    pub body: Value
+}
+
+
+impl Response {
+
+    fn ok(body: impl Serialize) -> Self {
+        Response { status: 200, body: serde_json::to_value(body).unwrap_or(Value::Null) }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Response { status, body: serde_json::json!({ "error": message.into() }) }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            413 => "Payload Too Large",
+            _ => "Internal Server Error"
+        }
+    }
+
+}
+
+
+/// JSON prediction server over a set of loaded models.
+///
+/// Routes:
+///
+/// * `GET /health` - Server status and number of models.
+/// * `GET /v1/models` - Every served model with its features and classes.
+/// * `POST /v1/models/{name}/predict` - Predictions for a body of
+///   `{"instances": [{"feature": value, ...}, ...]}` or a single
+///   feature object.
+///
+pub struct ModelServer {
+    models: BTreeMap<String, ServedModel>
+}
+
+
+impl ModelServer {
+
+    /// Server over models, keyed by the name each is served under
+    pub fn new(models: Vec<ServedModel>) -> Self {
+        ModelServer {
+            models: models.into_iter().map(|model| (model.name.clone(), model)).collect()
+        }
+    }
+
+    /// Models served, ordered by name
+    pub fn models(&self) -> impl Iterator<Item = &ServedModel> {
+        self.models.values()
+    }
+
+    /// Route a request to its handler.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - HTTP method of the request.
+    /// * `path` - Request path, a query string is ignored.
+    /// * `body` - Raw request body.
+    ///
+    pub fn handle(&self, method: &str, path: &str, body: &[u8]) -> Response {
+
+        let path = path.split('?').next().unwrap_or_default().trim_end_matches('/');
+        let segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+
+        match (method, segments.as_slice()) {
+            ("GET", ["health"]) => Response::ok(serde_json::json!({
+                "status": "ok",
+                "models": self.models.len()
+            })),
+            ("GET", ["v1", "models"]) => Response::ok(serde_json::json!({
+                "models": self.models().map(ServedModel::info).collect::<Vec<_>>()
+            })),
+            ("POST", ["v1", "models", name, "predict"]) => self.predict(name, body),
+            (_, ["health"]) | (_, ["v1", "models"]) | (_, ["v1", "models", _, "predict"]) => {
+                Response::error(405, format!("{method} is not allowed on {path}"))
+            },
+            _ => Response::error(404, format!("no route for {path}"))
+        }
+    }
+
+    fn predict(&self, name: &str, body: &[u8]) -> Response {
+
+        let Some(served) = self.models.get(name) else {
+            return Response::error(404, format!("no model named {name:?} is served"));
+        };
+
+        let instances = match serde_json::from_slice(body) {
+            Ok(Value::Object(mut request)) => match request.remove("instances") {
+                Some(Value::Array(instances)) => instances,
+                Some(_) => return Response::error(400, "instances must be an array of feature objects"),
+                None => vec![Value::Object(request)]
+            },
+            Ok(_) => return Response::error(400, "body must be a JSON object"),
+            Err(err) => return Response::error(400, format!("invalid JSON body: {err}"))
+        };
+
+        let instances: Vec<Map<String, Value>> = match instances
+            .into_iter()
+            .map(|instance| match instance {
+                Value::Object(instance) => Some(instance),
+                _ => None
+            })
+            .collect() {
+            Some(instances) => instances,
+            None => return Response::error(400, "every instance must be an object of named features")
+        };
+
+        match served.predict(&instances) {
+            Ok(predictions) => Response::ok(serde_json::json!({
+                "model": served.name,
+                "version": VersionSpec::Pinned(served.version).to_string(),
+                "predictions": predictions
+            })),
+            Err(message) => Response::error(400, message)
+        }
+    }
+
+    /// Accept connections until the listener fails, one thread per connection
+    pub fn serve(self, listener: TcpListener) -> PipelineResult<()> {
+        let server = Arc::new(self);
+        for stream in listener.incoming() {
+            let stream = stream.map_err(|err| PipelineError::artifact("prediction server", err))?;
+            let server = Arc::clone(&server);
+            thread::spawn(move || server.respond(stream));
+        }
+        Ok(())
+    }
+
+    /// Read one request from a connection and write its response
+    fn respond(&self, mut stream: TcpStream) {
+        let response = match read_request(&mut stream) {
+            Ok((method, path, body)) => self.handle(&method, &path, &body),
+            Err(response) => response
+        };
+        let body = response.body.to_string();
+        let _ = write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            response.status,
+            response.reason(),
+            body.len()
+        );
+        let _ = stream.flush();
+    }
+
+}
+
+
+/// Method, path and body of an HTTP/1.1 request
+fn read_request(stream: &mut TcpStream) -> Result<(String, String, Vec<u8>), Response> {
+
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    reader.read_line(&mut line).map_err(|err| Response::error(400, err.to_string()))?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err(Response::error(400, "malformed request line"));
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+
+    let mut length = 0;
+    loop {
+        line.clear();
+        reader.read_line(&mut line).map_err(|err| Response::error(400, err.to_string()))?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.trim().eq_ignore_ascii_case("content-length") {
+            length = value.trim().parse().map_err(|_| Response::error(400, "invalid Content-Length"))?;
+        }
+    }
+
+    if length > MAX_BODY_BYTES {
+        return Err(Response::error(413, format!("body is larger than {MAX_BODY_BYTES} bytes")));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).map_err(|err| Response::error(400, err.to_string()))?;
+    Ok((method, path, body))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::export::tests::exported;
+
+    /// Server over the hand-built export of every task
+    fn server() -> ModelServer {
+        let served = |name: &str, task| ServedModel {
+            name: name.to_string(),
+            version: 2,
+            stage: ModelStage::Production,
+            model: exported(task)
+        };
+        ModelServer::new(vec![
+            served("prices", Task::Regression),
+            served("churn", Task::Binary),
+            served("tiers", Task::Multiclass)
+        ])
+    }
+
+    fn post(server: &ModelServer, path: &str, body: &str) -> Response {
+        server.handle("POST", path, body.as_bytes())
+    }
+
+    /// Send a request to a server on localhost and read its status and JSON body
+    fn request(address: SocketAddr, method: &str, path: &str, body: &str) -> (u16, Value) {
+        let mut stream = TcpStream::connect(address).unwrap();
+        write!(stream, "{method} {path} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{body}", body.len()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    #[test]
+    fn lists_health_and_models() {
+        let server = server();
+
+        let health = server.handle("GET", "/health", b"");
+        assert_eq!(health, Response { status: 200, body: json!({ "status": "ok", "models": 3 }) });
+
+        let models = server.handle("GET", "/v1/models/?verbose=1", b"");
+        assert_eq!(models.status, 200);
+        let names: Vec<&Value> = models.body["models"].as_array().unwrap().iter().map(|model| &model["name"]).collect();
+        assert_eq!(names, [&json!("churn"), &json!("prices"), &json!("tiers")]);
+        assert_eq!(models.body["models"][0]["features"], json!(["a", "b"]));
+        assert_eq!(models.body["models"][0]["classes"], json!(["no", "yes"]));
+        assert_eq!(models.body["models"][0]["version"], json!("v2"));
+        assert_eq!(models.body["models"][1]["classes"], json!([]));
+    }
+
+    #[test]
+    fn predicts_instances_and_single_objects() {
+        let server = server();
+
+        let response = post(&server, "/v1/models/prices/predict", r#"{"instances": [{"a": 1, "b": 2}, {"b": 1, "a": 4.0}]}"#);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, json!({
+            "model": "prices",
+            "version": "v2",
+            "predictions": [{ "prediction": 3.0 }, { "prediction": 10.0 }]
+        }));
+
+        // A bare feature object is one instance and booleans count as 0 or 1
+        let response = post(&server, "/v1/models/churn/predict", r#"{"a": true, "b": 0}"#);
+        assert_eq!(response.status, 200);
+        let prediction = &response.body["predictions"][0];
+        assert_eq!((&prediction["prediction"], &prediction["label"]), (&json!(1.0), &json!("yes")));
+        let positive = 1.0 / (1.0 + (-1.0f64).exp());
+        assert!((prediction["probabilities"]["yes"].as_f64().unwrap() - positive).abs() < 1e-12);
+        assert!((prediction["probabilities"]["no"].as_f64().unwrap() - (1.0 - positive)).abs() < 1e-12);
+
+        let response = post(&server, "/v1/models/tiers/predict", r#"{"instances": [{"a": 1, "b": 2}, {"a": 4, "b": 1}]}"#);
+        let labels: Vec<&Value> = response.body["predictions"].as_array().unwrap().iter().map(|p| &p["label"]).collect();
+        assert_eq!(labels, [&json!("mid"), &json!("high")]);
+    }
+
+    #[test]
+    fn rejects_malformed_requests() {
+        let server = server();
+        let rejected = |body: &str, message: &str| {
+            let response = post(&server, "/v1/models/prices/predict", body);
+            assert_eq!(response.status, 400, "{body}");
+            let error = response.body["error"].as_str().unwrap();
+            assert!(error.contains(message), "{body}: {error}");
+        };
+
+        rejected(r#"{"a": 1, "b": "#, "invalid JSON body");
+        rejected("[1, 2]", "body must be a JSON object");
+        rejected(r#"{"instances": {"a": 1, "b": 2}}"#, "instances must be an array");
+        rejected(r#"{"instances": [[1, 2]]}"#, "every instance must be an object");
+
+        // Wrong feature counts are reported by feature name
+        rejected(r#"{"a": 1}"#, r#"instance 0 is missing feature "b""#);
+        rejected(r#"{"instances": [{"a": 1, "b": 2}, {"a": 1, "b": 2, "c": 3}]}"#, r#"instance 1 has unknown feature "c""#);
+        rejected(r#"{"a": 1, "b": "2"}"#, r#"feature "b" is not a number"#);
+    }
+
+    #[test]
+    fn rejects_unknown_routes_and_methods() {
+        let server = server();
+
+        assert_eq!(post(&server, "/v1/models/missing/predict", r#"{"a": 1, "b": 2}"#).status, 404);
+        assert_eq!(server.handle("GET", "/v2/models", b"").status, 404);
+        assert_eq!(server.handle("GET", "/v1/models/prices/predict", b"").status, 405);
+        assert_eq!(server.handle("DELETE", "/health", b"").status, 405);
+        assert_eq!(server.handle("POST", "/v1/models", b"{}").status, 405);
+    }
+
+    #[test]
+    fn serves_over_http() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || server().serve(listener));
+
+        let (status, health) = request(address, "GET", "/health", "");
+        assert_eq!((status, &health["models"]), (200, &json!(3)));
+
+        let (status, response) = request(address, "POST", "/v1/models/prices/predict", r#"{"a": 1, "b": 2}"#);
+        assert_eq!((status, &response["predictions"][0]["prediction"]), (200, &json!(3.0)));
+
+        let (status, response) = request(address, "POST", "/v1/models/prices/predict", "{");
+        assert_eq!(status, 400);
+        assert!(response["error"].as_str().unwrap().contains("invalid JSON body"));
+    }
+
+}
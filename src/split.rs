@@ -0,0 +1,493 @@
+use std::collections::BTreeMap;
+
+use ndarray::{Array2, ArrayView1, Axis};
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use crate::config::SplitKind;
+use crate::error::*;
+
+
+/// Fractions of rows held out of training
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SplitSizes {
+
+    /// Fraction of rows assigned to the validation partition
+    pub validation: f64,
+
+    /// Fraction of rows assigned to the test partition
+    pub test: f64
+}
+
+
+impl SplitSizes {
+
+    /// Number of validation and test rows out of `num_rows`
+    pub fn held_out(&self, num_rows: usize) -> (usize, usize) {
+        let test = (num_rows as f64 * self.test) as usize;
+        let validation = (num_rows as f64 * self.validation) as usize;
+        (validation, test)
+    }
+
+}
+
+
+/// Row indices assigned to each partition
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SplitIndices {
+
+    /// Rows used to fit encoders and train the model
+    pub train: Vec<usize>,
+
+    /// Rows used for model selection
+    pub validation: Vec<usize>,
+
+    /// Rows held out for final evaluation
+    pub test: Vec<usize>
+}
+
+
+/// Rows of an array selected for each partition
+#[derive(Debug, Clone)]
+pub struct Partitions {
+
+    /// Training rows
+    pub train: Array2<f64>,
+
+    /// Validation rows
+    pub validation: Array2<f64>,
+
+    /// Test rows
+    pub test: Array2<f64>
+}
+
+
+/// Split of a dataset with both the index sets and the partitioned arrays
+#[derive(Debug, Clone)]
+pub struct DatasetSplit {
+
+    /// Row indices of each partition
+    pub indices: SplitIndices,
+
+    /// Feature partitions
+    pub x: Partitions,
+
+    /// Target partitions
+    pub y: Partitions
+}
+
+
+impl SplitIndices {
+
+    /// Select rows of each partition from data
+    pub fn select(&self, data: &Array2<f64>) -> Partitions {
+        Partitions {
+            train: data.select(Axis(0), &self.train),
+            validation: data.select(Axis(0), &self.validation),
+            test: data.select(Axis(0), &self.test)
+        }
+    }
+
+    /// Split features and targets along the index sets
+    pub fn apply(self, x: &Array2<f64>, y: &Array2<f64>) -> DatasetSplit {
+        DatasetSplit {
+            x: self.select(x),
+            y: self.select(y),
+            indices: self
+        }
+    }
+
+}
+
+
+/// Assign rows to train, validation and test partitions.
+///
+/// # Arguments
+///
+/// * `kind` - Strategy used to assign rows.
+/// * `sizes` - Fractions of rows held out for validation and testing.
+/// * `labels` - Class label of each row, required for stratified splits.
+/// * `groups` - Group id of each row, required for grouped splits.
+/// * `rng` - Random source used to shuffle rows, classes or groups.
+///
+pub fn split_indices<R: Rng + ?Sized>(
+    kind: SplitKind,
+    num_rows: usize,
+    sizes: SplitSizes,
+    labels: Option<ArrayView1<f64>>,
+    groups: Option<ArrayView1<f64>>,
+    rng: &mut R) -> PipelineResult<SplitIndices> {
+
+    match kind {
+        SplitKind::Chronological => {
+            Ok(partition((0..num_rows).collect(), sizes))
+        },
+        SplitKind::Random => {
+            let mut rows: Vec<usize> = (0..num_rows).collect();
+            rows.shuffle(rng);
+            Ok(partition(rows, sizes))
+        },
+        SplitKind::Stratified => {
+            let labels = column_for("stratified split", "labels", labels, num_rows)?;
+            let mut indices = SplitIndices::default();
+            for (_, mut rows) in bucket(0..num_rows, &labels) {
+                rows.shuffle(rng);
+                let class = partition(rows, sizes);
+                indices.train.extend(class.train);
+                indices.validation.extend(class.validation);
+                indices.test.extend(class.test);
+            }
+
+            // Classes were appended in turn, mix them back together
+            indices.train.shuffle(rng);
+            indices.validation.shuffle(rng);
+            indices.test.shuffle(rng);
+            Ok(indices)
+        },
+        SplitKind::Grouped => {
+            let groups = column_for("grouped split", "groups", groups, num_rows)?;
+            let mut buckets: Vec<Vec<usize>> = bucket(0..num_rows, &groups).into_values().collect();
+            buckets.shuffle(rng);
+
+            let (validation_rows, test_rows) = sizes.held_out(num_rows);
+            let mut indices = SplitIndices::default();
+            for rows in buckets {
+                if indices.test.len() < test_rows {
+                    indices.test.extend(rows);
+                } else if indices.validation.len() < validation_rows {
+                    indices.validation.extend(rows);
+                } else {
+                    indices.train.extend(rows);
+                }
+            }
+            Ok(indices)
+        }
+    }
+}
+
+
+/// Partition ordered rows, training on the leading rows
+fn partition(rows: Vec<usize>, sizes: SplitSizes) -> SplitIndices {
+    let (validation_rows, test_rows) = sizes.held_out(rows.len());
+    let train_end = rows.len() - validation_rows - test_rows;
+    let validation_end = train_end + validation_rows;
+    SplitIndices {
+        train: rows[..train_end].to_vec(),
+        validation: rows[train_end..validation_end].to_vec(),
+        test: rows[validation_end..].to_vec()
+    }
+}
+
+
+/// Rows grouped by their value, keyed in a stable order
+fn bucket(rows: impl IntoIterator<Item = usize>, values: &ArrayView1<f64>) -> BTreeMap<u64, Vec<usize>> {
+    let mut buckets: BTreeMap<u64, Vec<usize>> = BTreeMap::new();
+    for row in rows {
+        buckets.entry(values[row].to_bits()).or_default().push(row);
+    }
+    buckets
+}
+
+
+fn column_for<'a>(
+    context: &str,
+    name: &str,
+    values: Option<ArrayView1<'a, f64>>,
+    num_rows: usize) -> PipelineResult<ArrayView1<'a, f64>> {
+
+    let values = values.ok_or(PipelineError::Config(format!("{context} requires {name}")))?;
+    if values.len() != num_rows {
+        return Err(PipelineError::ShapeMismatch {
+            context: context.to_string(),
+            expected: format!("{num_rows} {name}"),
+            found: format!("{} {name}", values.len())
+        });
+    }
+    Ok(values)
+}
+
+
+/// Train and validation rows of a single cross-validation fold
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fold {
+
+    /// Rows the fold model is fit on
+    pub train: Vec<usize>,
+
+    /// Rows the fold model is scored on
+    pub validation: Vec<usize>
+}
+
+
+/// Assign rows to `k` cross-validation folds.
+///
+/// Chronological folds are contiguous blocks in row order, random folds are
+/// shuffled, stratified folds deal each class round robin so every fold keeps
+/// the class proportions, and grouped folds keep every group in one fold.
+///
+/// # Arguments
+///
+/// * `kind` - Strategy used to assign rows to folds.
+/// * `rows` - Pool of row indices to fold.
+/// * `k` - Number of folds.
+/// * `labels` - Class label of every row, required for stratified folds.
+/// * `groups` - Group id of every row, required for grouped folds.
+/// * `rng` - Random source used to shuffle rows or groups.
+///
+pub fn k_fold<R: Rng + ?Sized>(
+    kind: SplitKind,
+    rows: &[usize],
+    k: usize,
+    labels: Option<ArrayView1<f64>>,
+    groups: Option<ArrayView1<f64>>,
+    rng: &mut R) -> PipelineResult<Vec<Fold>> {
+
+    if k < 2 || k > rows.len() {
+        return Err(PipelineError::Config(format!(
+            "cross validation needs between 2 and {} folds, found {k}", rows.len()
+        )));
+    }
+
+    let mut assigned: Vec<Vec<usize>> = vec![Vec::new(); k];
+    match kind {
+        SplitKind::Chronological | SplitKind::Random => {
+            let mut order = rows.to_vec();
+            if kind == SplitKind::Random {
+                order.shuffle(rng);
+            }
+            let (size, extra) = (order.len() / k, order.len() % k);
+            let mut start = 0;
+            for (fold, block) in assigned.iter_mut().enumerate() {
+                let end = start + size + usize::from(fold < extra);
+                block.extend_from_slice(&order[start..end]);
+                start = end;
+            }
+        },
+        SplitKind::Stratified => {
+            let labels = labels.ok_or(PipelineError::Config("stratified folds require labels".to_string()))?;
+            let mut next = 0;
+            for (_, mut class_rows) in bucket(rows.iter().copied(), &labels) {
+                class_rows.shuffle(rng);
+                for row in class_rows {
+                    assigned[next % k].push(row);
+                    next += 1;
+                }
+            }
+        },
+        SplitKind::Grouped => {
+            let groups = groups.ok_or(PipelineError::Config("grouped folds require groups".to_string()))?;
+            let mut buckets: Vec<Vec<usize>> = bucket(rows.iter().copied(), &groups).into_values().collect();
+            buckets.shuffle(rng);
+            for group_rows in buckets {
+                let smallest = (0..k).min_by_key(|&fold| assigned[fold].len()).unwrap_or(0);
+                assigned[smallest].extend(group_rows);
+            }
+        }
+    }
+
+    if assigned.iter().any(|fold| fold.is_empty()) {
+        return Err(PipelineError::Config(format!("unable to fill {k} non empty folds")));
+    }
+
+    let folds = (0..k)
+        .map(|fold| Fold {
+            train: assigned
+                .iter()
+                .enumerate()
+                .filter(|(other, _)| *other != fold)
+                .flat_map(|(_, rows)| rows.iter().copied())
+                .collect(),
+            validation: assigned[fold].clone()
+        })
+        .collect();
+
+    Ok(folds)
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::BTreeSet;
+
+    use ndarray::Array1;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::*;
+
+    const ROWS: usize = 100;
+
+    const SIZES: SplitSizes = SplitSizes { validation: 0.1, test: 0.2 };
+
+    const KINDS: [SplitKind; 4] = [
+        SplitKind::Random,
+        SplitKind::Stratified,
+        SplitKind::Grouped,
+        SplitKind::Chronological
+    ];
+
+    /// 60 rows of class 0, 30 of class 1 and 10 of class 2, interleaved
+    fn labels() -> Array1<f64> {
+        Array1::from_iter((0..ROWS).map(|row| match row % 10 {
+            0..=5 => 0.0,
+            6..=8 => 1.0,
+            _ => 2.0
+        }))
+    }
+
+    /// 20 groups of 5 consecutive rows
+    fn groups() -> Array1<f64> {
+        Array1::from_iter((0..ROWS).map(|row| (row / 5) as f64))
+    }
+
+    fn split(kind: SplitKind, seed: u64) -> SplitIndices {
+        let (labels, groups) = (labels(), groups());
+        let mut rng = StdRng::seed_from_u64(seed);
+        split_indices(kind, ROWS, SIZES, Some(labels.view()), Some(groups.view()), &mut rng).unwrap()
+    }
+
+    fn folds(kind: SplitKind, rows: &[usize], seed: u64) -> Vec<Fold> {
+        let (labels, groups) = (labels(), groups());
+        let mut rng = StdRng::seed_from_u64(seed);
+        k_fold(kind, rows, 5, Some(labels.view()), Some(groups.view()), &mut rng).unwrap()
+    }
+
+    fn class_counts(rows: &[usize]) -> [usize; 3] {
+        let labels = labels();
+        let mut counts = [0; 3];
+        for &row in rows {
+            counts[labels[row] as usize] += 1;
+        }
+        counts
+    }
+
+    fn group_set(rows: &[usize]) -> BTreeSet<usize> {
+        rows.iter().map(|row| row / 5).collect()
+    }
+
+    /// Asserts the parts share no row and together hold exactly `rows`
+    fn assert_partition(parts: &[&[usize]], rows: &[usize]) {
+        let mut seen: Vec<usize> = parts.iter().flat_map(|part| part.iter().copied()).collect();
+        seen.sort_unstable();
+        let mut expected = rows.to_vec();
+        expected.sort_unstable();
+        assert_eq!(seen, expected);
+    }
+
+    #[test]
+    fn splits_are_disjoint_and_cover_every_row() {
+        let all: Vec<usize> = (0..ROWS).collect();
+        for kind in KINDS {
+            let indices = split(kind, 7);
+            assert_partition(&[&indices.train, &indices.validation, &indices.test], &all);
+            assert!(!indices.validation.is_empty() && !indices.test.is_empty(), "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn splits_hold_out_the_configured_fractions() {
+        for kind in [SplitKind::Random, SplitKind::Chronological] {
+            let indices = split(kind, 7);
+            assert_eq!((indices.train.len(), indices.validation.len(), indices.test.len()), (70, 10, 20));
+        }
+    }
+
+    #[test]
+    fn stratified_split_keeps_class_proportions() {
+        let indices = split(SplitKind::Stratified, 7);
+        assert_eq!(class_counts(&indices.train), [42, 21, 7]);
+        assert_eq!(class_counts(&indices.validation), [6, 3, 1]);
+        assert_eq!(class_counts(&indices.test), [12, 6, 2]);
+    }
+
+    #[test]
+    fn grouped_split_keeps_groups_together() {
+        for seed in 0..10 {
+            let indices = split(SplitKind::Grouped, seed);
+            let (train, validation, test) = (group_set(&indices.train), group_set(&indices.validation), group_set(&indices.test));
+            assert!(train.is_disjoint(&validation) && train.is_disjoint(&test) && validation.is_disjoint(&test));
+        }
+    }
+
+    #[test]
+    fn chronological_split_keeps_row_order() {
+        let indices = split(SplitKind::Chronological, 7);
+        assert_eq!(indices.train, (0..70).collect::<Vec<_>>());
+        assert_eq!(indices.validation, (70..80).collect::<Vec<_>>());
+        assert_eq!(indices.test, (80..100).collect::<Vec<_>>());
+        assert_eq!(indices, split(SplitKind::Chronological, 8));
+    }
+
+    #[test]
+    fn same_seed_gives_same_split() {
+        for kind in KINDS {
+            assert_eq!(split(kind, 7), split(kind, 7), "{kind:?}");
+            assert_eq!(folds(kind, &(0..ROWS).collect::<Vec<_>>(), 7), folds(kind, &(0..ROWS).collect::<Vec<_>>(), 7), "{kind:?}");
+        }
+        for kind in [SplitKind::Random, SplitKind::Stratified, SplitKind::Grouped] {
+            assert_ne!(split(kind, 7), split(kind, 8), "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn split_requires_labels_and_groups() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for kind in [SplitKind::Stratified, SplitKind::Grouped] {
+            let err = split_indices(kind, ROWS, SIZES, None, None, &mut rng).unwrap_err();
+            assert_eq!(err.kind(), "config");
+        }
+        let short = Array1::zeros(ROWS - 1);
+        let err = split_indices(SplitKind::Stratified, ROWS, SIZES, Some(short.view()), None, &mut rng).unwrap_err();
+        assert_eq!(err.kind(), "shape_mismatch");
+    }
+
+    #[test]
+    fn folds_are_disjoint_and_cover_the_pool() {
+        // The pool excludes held out rows, folds must never reach them
+        let pool: Vec<usize> = (10..90).collect();
+        for kind in KINDS {
+            let folds = folds(kind, &pool, 7);
+            assert_eq!(folds.len(), 5);
+            let validation: Vec<&[usize]> = folds.iter().map(|fold| fold.validation.as_slice()).collect();
+            assert_partition(&validation, &pool);
+            for fold in &folds {
+                assert_partition(&[&fold.train, &fold.validation], &pool);
+            }
+        }
+    }
+
+    #[test]
+    fn stratified_folds_keep_class_proportions() {
+        let all: Vec<usize> = (0..ROWS).collect();
+        for fold in folds(SplitKind::Stratified, &all, 7) {
+            assert_eq!(class_counts(&fold.validation), [12, 6, 2]);
+        }
+    }
+
+    #[test]
+    fn grouped_folds_keep_groups_together() {
+        let all: Vec<usize> = (0..ROWS).collect();
+        for fold in folds(SplitKind::Grouped, &all, 7) {
+            assert!(group_set(&fold.train).is_disjoint(&group_set(&fold.validation)));
+        }
+    }
+
+    #[test]
+    fn chronological_folds_are_ordered_blocks() {
+        let all: Vec<usize> = (0..ROWS).collect();
+        for (idx, fold) in folds(SplitKind::Chronological, &all, 7).iter().enumerate() {
+            assert_eq!(fold.validation, (idx * 20..(idx + 1) * 20).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn k_fold_rejects_fold_counts_out_of_range() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for k in [0, 1, 4] {
+            let err = k_fold(SplitKind::Random, &[0, 1, 2], k, None, None, &mut rng).unwrap_err();
+            assert_eq!(err.kind(), "config");
+        }
+    }
+
+}
@@ -0,0 +1,548 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::hash::{DefaultHasher, Hasher};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Serialize, Deserialize};
+
+use crate::artifact::{read_json, write_json};
+use crate::config::*;
+use crate::error::*;
+use crate::evaluation::{EVALUATION_FILE, EVALUATION_SUMMARY_FILE};
+use crate::registry::{ModelRegistry, ModelStage, Promotion};
+use crate::schema::{checksum, SCHEMA_VERSION};
+
+
+/// Directory every pipeline's artifact store lives under
+pub const MODELS_DIR: &str = "models";
+
+/// File describing a saved model version
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// File dendritic saves model parameters to
+pub const PARAMETERS_FILE: &str = "parameters.json";
+
+/// Directory dendritic saves a model's computation graph to
+pub const GRAPH_DIR: &str = "regression_exp";
+
+/// Reports written into a version after it is saved, left out of its manifest
+const REPORT_FILES: [&str; 3] = [MANIFEST_FILE, EVALUATION_FILE, EVALUATION_SUMMARY_FILE];
+
+
+/// Model version requested from an artifact store
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum VersionSpec {
+
+    /// Most recently trained version
+    #[default]
+    Latest,
+
+    /// Specific version number
+    Pinned(u32),
+
+    /// Newest version in a registry stage
+    Stage(ModelStage)
+}
+
+
+impl VersionSpec {
+
+    /// Parse `latest`, a stage name, `v<n>` or `<n>`
+    pub fn parse(value: &str) -> Result<Self, String> {
+        if value == "latest" {
+            return Ok(VersionSpec::Latest);
+        }
+        if let Some(stage) = ModelStage::parse(value) {
+            return Ok(VersionSpec::Stage(stage));
+        }
+        value
+            .strip_prefix('v')
+            .unwrap_or(value)
+            .parse()
+            .map(VersionSpec::Pinned)
+            .map_err(|_| format!("invalid model version {value:?}, expected latest, a stage or v<n>"))
+    }
+
+}
+
+
+impl fmt::Display for VersionSpec {
+
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VersionSpec::Latest => write!(f, "latest"),
+            VersionSpec::Pinned(version) => write!(f, "v{version}"),
+            VersionSpec::Stage(stage) => write!(f, "{stage}")
+        }
+    }
+
+}
+
+
+impl TryFrom<String> for VersionSpec {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, String> {
+        VersionSpec::parse(&value)
+    }
+}
+
+
+impl From<VersionSpec> for String {
+    fn from(spec: VersionSpec) -> String {
+        spec.to_string()
+    }
+}
+
+
+/// Description of a saved model version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+
+    /// Name of experiment the version was trained by
+    pub pipeline: String,
+
+    /// Version number, starting at 1
+    pub version: u32,
+
+    /// UTC time the version was saved
+    pub created_at: String,
+
+    /// Kind of model saved
+    pub model: ModelKind,
+
+    /// Seed of the training run
+    pub seed: u64,
+
+    /// Files of the version relative to its directory
+    pub files: Vec<String>,
+
+    /// Artifact schema the version was written with, 1 for manifests that predate it
+    #[serde(default = "first_schema")]
+    pub schema_version: u32,
+
+    /// SHA-256 of every file in `files`
+    #[serde(default)]
+    pub checksums: BTreeMap<String, String>,
+
+    /// Directory the version was imported from by a migration
+    #[serde(default)]
+    pub source: Option<String>
+}
+
+
+fn first_schema() -> u32 {
+    1
+}
+
+
+impl Manifest {
+
+    /// Directory name of the version
+    pub fn name(&self) -> String {
+        VersionSpec::Pinned(self.version).to_string()
+    }
+
+}
+
+
+/// Versioned model artifacts of a single pipeline.
+///
+/// Every training run saves to a new `<root>/v<n>/` directory, which is
+/// only listed once its manifest has been written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactStore {
+
+    /// Directory holding the pipeline's versions
+    root: String
+}
+
+
+impl ArtifactStore {
+
+    /// Open store rooted at a pipeline's output directory
+    pub fn new(root: &str) -> Self {
+        ArtifactStore { root: root.trim_end_matches('/').to_string() }
+    }
+
+    /// Directory holding the pipeline's versions
+    pub fn root(&self) -> &str {
+        &self.root
+    }
+
+    /// Directory of a version
+    pub fn version_dir(&self, version: u32) -> String {
+        format!("{}/{}", self.root, VersionSpec::Pinned(version))
+    }
+
+    /// Manifests of every complete version, oldest first
+    pub fn versions(&self) -> PipelineResult<Vec<Manifest>> {
+        let mut manifests = Vec::new();
+        for version in self.version_numbers()? {
+            let dir = self.version_dir(version);
+            if Path::new(&dir).join(MANIFEST_FILE).is_file() {
+                manifests.push(read_json(&dir, MANIFEST_FILE)?);
+            }
+        }
+        Ok(manifests)
+    }
+
+    /// Manifest of the requested version
+    pub fn resolve(&self, spec: VersionSpec) -> PipelineResult<Manifest> {
+        let versions = self.versions()?;
+        let found = match spec {
+            VersionSpec::Latest => versions.last(),
+            VersionSpec::Pinned(version) => versions.iter().find(|manifest| manifest.version == version),
+            VersionSpec::Stage(stage) => {
+                return self.registry()?
+                    .latest_in(stage, &versions)
+                    .cloned()
+                    .ok_or(PipelineError::Registry(format!("no version of {} is in {stage}", self.root)));
+            }
+        };
+        found.cloned().ok_or(PipelineError::MissingFile { path: format!("{}/{spec}", self.root) })
+    }
+
+    /// Directory of the requested version
+    pub fn dir(&self, spec: VersionSpec) -> PipelineResult<String> {
+        self.resolve(spec).map(|manifest| self.version_dir(manifest.version))
+    }
+
+    /// Registry of the stages versions are in
+    pub fn registry(&self) -> PipelineResult<ModelRegistry> {
+        ModelRegistry::load(&self.root)
+    }
+
+    /// Move a version to another registry stage and save the registry.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - Version to move, resolved before the move.
+    /// * `to` - Stage to move the version to.
+    /// * `by` - Person or process making the change.
+    /// * `reason` - Why the version is moved.
+    ///
+    pub fn promote(&self, spec: VersionSpec, to: ModelStage, by: &str, reason: &str) -> PipelineResult<Vec<Promotion>> {
+        let manifest = self.resolve(spec)?;
+        let mut registry = self.registry()?;
+        let recorded = registry.promote(manifest.version, to, by, reason)?;
+        registry.save(&self.root)?;
+        Ok(recorded)
+    }
+
+    /// Create the directory of the next version
+    pub fn create(&self) -> PipelineResult<(u32, String)> {
+        let version = self.version_numbers()?.last().map_or(1, |last| last + 1);
+        let dir = self.version_dir(version);
+        fs::create_dir_all(&dir).map_err(|err| PipelineError::artifact(&dir, err))?;
+        Ok((version, dir))
+    }
+
+    /// Write the manifest of a version once all of its files are saved
+    pub fn commit(&self, version: u32, config: &ExperimentConfig) -> PipelineResult<Manifest> {
+        let mut manifest = Manifest {
+            pipeline: config.name.clone(),
+            version,
+            created_at: timestamp(),
+            model: config.model.kind,
+            seed: config.seed,
+            files: Vec::new(),
+            schema_version: SCHEMA_VERSION,
+            checksums: BTreeMap::new(),
+            source: None
+        };
+        self.seal(&mut manifest)?;
+        Ok(manifest)
+    }
+
+    /// List and checksum the files of a version and write its manifest
+    pub fn seal(&self, manifest: &mut Manifest) -> PipelineResult<()> {
+        let dir = self.version_dir(manifest.version);
+        let mut files = Vec::new();
+        list_files(Path::new(&dir), "", &mut files).map_err(|err| PipelineError::artifact(&dir, err))?;
+        files.retain(|file| !REPORT_FILES.contains(&file.as_str()));
+        files.sort();
+
+        manifest.checksums = files
+            .iter()
+            .map(|file| checksum(&format!("{dir}/{file}")).map(|sum| (file.clone(), sum)))
+            .collect::<PipelineResult<_>>()?;
+        manifest.files = files;
+        manifest.schema_version = SCHEMA_VERSION;
+        write_json(&dir, MANIFEST_FILE, manifest)
+    }
+
+    /// Remove graph files of every version that dendritic would not load.
+    ///
+    /// dendritic names graph files after a hash of the graph path they were
+    /// saved to and loads whichever one it lists last, so files left by
+    /// saves to another path can shadow the current graph. Only files that
+    /// do not match the graph path in `parameters.json` are removed. A store
+    /// root saved to directly, as before versioning, is pruned as well.
+    /// Removed files are dropped from a sealed version's manifest, whose
+    /// other checksums are left as they were.
+    ///
+    /// Returns the removed paths.
+    pub fn prune(&self) -> PipelineResult<Vec<String>> {
+        let mut dirs: Vec<String> = self.version_numbers()?
+            .into_iter()
+            .map(|version| self.version_dir(version))
+            .collect();
+        dirs.push(self.root.clone());
+
+        let mut removed = Vec::new();
+        for dir in dirs {
+            // The graph path a model loads from is recorded in its parameters
+            let Ok(parameters) = read_json::<serde_json::Value>(&dir, PARAMETERS_FILE) else { continue };
+            let Some(graph_path) = parameters["graph_path"].as_str() else { continue };
+            let current = graph_prefix(graph_path);
+
+            let graph_dir = format!("{dir}/{GRAPH_DIR}");
+            let Ok(entries) = fs::read_dir(&graph_dir) else { continue };
+            let mut pruned = Vec::new();
+            for entry in entries.filter_map(|entry| entry.ok()) {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let is_graph = name.ends_with("_nodes.json") || name.ends_with("_metadata.json");
+                if is_graph && !name.starts_with(&format!("{current}_")) {
+                    let path = format!("{graph_dir}/{name}");
+                    fs::remove_file(&path).map_err(|err| PipelineError::artifact(&path, err))?;
+                    pruned.push(format!("{GRAPH_DIR}/{name}"));
+                    removed.push(path);
+                }
+            }
+
+            if let Ok(mut manifest) = read_json::<Manifest>(&dir, MANIFEST_FILE)
+                && manifest.files.iter().any(|file| pruned.contains(file)) {
+                manifest.files.retain(|file| !pruned.contains(file));
+                manifest.checksums.retain(|file, _| !pruned.contains(file));
+                write_json(&dir, MANIFEST_FILE, &manifest)?;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Numbers of every version directory, complete or not, in order
+    fn version_numbers(&self) -> PipelineResult<Vec<u32>> {
+        let entries = match fs::read_dir(&self.root) {
+            Ok(entries) => entries,
+            Err(_) => return Ok(Vec::new())
+        };
+
+        let mut versions: Vec<u32> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().to_string_lossy().strip_prefix('v')?.parse().ok())
+            .collect();
+        versions.sort();
+        Ok(versions)
+    }
+
+}
+
+
+/// Prefix dendritic gives the graph files it saves under a graph path
+pub fn graph_prefix(graph_path: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(graph_path.as_bytes());
+    hasher.finish()
+}
+
+
+/// Current UTC time formatted as `YYYY-MM-DDTHH:MM:SSZ`
+pub fn timestamp() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+
+    // Civil date from days since epoch (Howard Hinnant's algorithm)
+    let (days, time) = ((seconds / 86_400) as i64, seconds % 86_400);
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3_600, time % 3_600 / 60, time % 60
+    )
+}
+
+
+fn list_files(dir: &Path, prefix: &str, files: &mut Vec<String>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = format!("{prefix}{}", entry.file_name().to_string_lossy());
+        if entry.path().is_dir() {
+            list_files(&entry.path(), &format!("{name}/"), files)?;
+        } else {
+            files.push(name);
+        }
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::schema::checksum;
+
+    /// Empty store in a temporary directory
+    fn store(name: &str) -> ArtifactStore {
+        let root = std::env::temp_dir().join("crate_store_tests").join(name);
+        let _ = fs::remove_dir_all(&root);
+        ArtifactStore::new(&root.to_string_lossy())
+    }
+
+    /// Create and commit a version holding a parameter file
+    fn save(store: &ArtifactStore) -> Manifest {
+        let (version, dir) = store.create().unwrap();
+        fs::write(format!("{dir}/{PARAMETERS_FILE}"), format!("{{\"version\": {version}}}")).unwrap();
+        store.commit(version, &crate::iris::config()).unwrap()
+    }
+
+    #[test]
+    fn parses_version_specs() {
+        for (value, spec) in [
+            ("latest", VersionSpec::Latest),
+            ("v3", VersionSpec::Pinned(3)),
+            ("3", VersionSpec::Pinned(3)),
+            ("production", VersionSpec::Stage(ModelStage::Production))
+        ] {
+            assert_eq!(VersionSpec::parse(value), Ok(spec));
+        }
+        assert_eq!(VersionSpec::Pinned(3).to_string(), "v3");
+        assert!(VersionSpec::parse("v").is_err() && VersionSpec::parse("newest").is_err());
+    }
+
+    #[test]
+    fn resolves_committed_versions() {
+        let store = store("resolve");
+        let err = store.resolve(VersionSpec::Latest).unwrap_err();
+        assert_eq!(err.kind(), "missing_file");
+
+        save(&store);
+        save(&store);
+        assert_eq!(store.resolve(VersionSpec::Latest).unwrap().version, 2);
+        assert_eq!(store.resolve(VersionSpec::Pinned(1)).unwrap().version, 1);
+        assert_eq!(store.dir(VersionSpec::Pinned(1)).unwrap(), store.version_dir(1));
+        assert_eq!(store.resolve(VersionSpec::Pinned(4)).unwrap_err().kind(), "missing_file");
+
+        // A version without a manifest is not listed, but its number is never reused
+        let (version, _) = store.create().unwrap();
+        assert_eq!(version, 3);
+        assert_eq!(store.versions().unwrap().len(), 2);
+        assert_eq!(store.resolve(VersionSpec::Latest).unwrap().version, 2);
+        assert_eq!(store.create().unwrap().0, 4);
+    }
+
+    #[test]
+    fn resolves_stages_through_the_registry() {
+        let store = store("stages");
+        save(&store);
+        save(&store);
+        assert_eq!(store.resolve(VersionSpec::Stage(ModelStage::Candidate)).unwrap().version, 2);
+        assert_eq!(store.resolve(VersionSpec::Stage(ModelStage::Staging)).unwrap_err().kind(), "registry");
+
+        store.promote(VersionSpec::Pinned(1), ModelStage::Staging, "tests", "review").unwrap();
+        assert_eq!(store.resolve(VersionSpec::Stage(ModelStage::Staging)).unwrap().version, 1);
+        assert_eq!(store.registry().unwrap().stage(1), ModelStage::Staging);
+        assert_eq!(store.promote(VersionSpec::Pinned(9), ModelStage::Staging, "tests", "review").unwrap_err().kind(), "missing_file");
+    }
+
+    #[test]
+    fn seal_checksums_every_file_but_reports() {
+        let store = store("seal");
+        let (version, dir) = store.create().unwrap();
+        fs::create_dir_all(format!("{dir}/{GRAPH_DIR}")).unwrap();
+        fs::write(format!("{dir}/{PARAMETERS_FILE}"), "{}").unwrap();
+        fs::write(format!("{dir}/{GRAPH_DIR}/1_nodes.json"), "[]").unwrap();
+        fs::write(format!("{dir}/{EVALUATION_FILE}"), "{}").unwrap();
+
+        let mut manifest = store.commit(version, &crate::iris::config()).unwrap();
+        let graph = format!("{GRAPH_DIR}/1_nodes.json");
+        assert_eq!(manifest.files, [PARAMETERS_FILE.to_string(), graph.clone()]);
+        assert_eq!(manifest.checksums[&graph], checksum(&format!("{dir}/{graph}")).unwrap());
+        assert_eq!((manifest.pipeline.as_str(), manifest.schema_version), ("iris", SCHEMA_VERSION));
+        assert_eq!(store.resolve(VersionSpec::Latest).unwrap(), manifest);
+
+        // Resealing picks up files written since
+        fs::write(format!("{dir}/{PARAMETERS_FILE}"), "{\"changed\": true}").unwrap();
+        store.seal(&mut manifest).unwrap();
+        assert_eq!(manifest.checksums[PARAMETERS_FILE], checksum(&format!("{dir}/{PARAMETERS_FILE}")).unwrap());
+        assert_eq!(store.resolve(VersionSpec::Latest).unwrap(), manifest);
+    }
+
+    #[test]
+    fn prune_removes_only_graphs_other_paths_saved() {
+        let store = store("prune");
+        let (_, dir) = store.create().unwrap();
+        let graph_dir = format!("{dir}/{GRAPH_DIR}");
+        fs::create_dir_all(&graph_dir).unwrap();
+        fs::write(format!("{dir}/{PARAMETERS_FILE}"), format!("{{\"graph_path\": \"{graph_dir}\"}}")).unwrap();
+
+        let (current, stale) = (graph_prefix(&graph_dir), graph_prefix("elsewhere"));
+        let files = [
+            format!("{current}_nodes.json"),
+            format!("{current}_metadata.json"),
+            format!("{stale}_nodes.json"),
+            format!("{stale}_metadata.json"),
+            "notes.txt".to_string()
+        ];
+        for file in &files {
+            fs::write(format!("{graph_dir}/{file}"), "[]").unwrap();
+        }
+
+        // A store root saved to directly, as before versioning, is pruned too
+        let legacy = format!("{}/{GRAPH_DIR}", store.root());
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(format!("{}/{PARAMETERS_FILE}", store.root()), format!("{{\"graph_path\": \"{legacy}\"}}")).unwrap();
+        fs::write(format!("{legacy}/{stale}_nodes.json"), "[]").unwrap();
+
+        let mut removed = store.prune().unwrap();
+        removed.sort();
+        let mut expected = vec![
+            format!("{graph_dir}/{stale}_nodes.json"),
+            format!("{graph_dir}/{stale}_metadata.json"),
+            format!("{legacy}/{stale}_nodes.json")
+        ];
+        expected.sort();
+        assert_eq!(removed, expected);
+        for file in [&files[0], &files[1], &files[4]] {
+            assert!(Path::new(&format!("{graph_dir}/{file}")).is_file(), "{file}");
+        }
+        assert!(store.prune().unwrap().is_empty());
+    }
+
+    #[test]
+    fn prune_drops_removed_files_from_sealed_manifests() {
+        let store = store("prune_sealed");
+        let (version, dir) = store.create().unwrap();
+        let graph_dir = format!("{dir}/{GRAPH_DIR}");
+        fs::create_dir_all(&graph_dir).unwrap();
+        fs::write(format!("{dir}/{PARAMETERS_FILE}"), format!("{{\"graph_path\": \"{graph_dir}\"}}")).unwrap();
+        let (current, stale) = (graph_prefix(&graph_dir), graph_prefix("elsewhere"));
+        fs::write(format!("{graph_dir}/{current}_nodes.json"), "[]").unwrap();
+        fs::write(format!("{graph_dir}/{stale}_nodes.json"), "[]").unwrap();
+        let sealed = store.commit(version, &crate::iris::config()).unwrap();
+        let stale = format!("{GRAPH_DIR}/{stale}_nodes.json");
+        assert!(sealed.files.contains(&stale));
+
+        assert_eq!(store.prune().unwrap().len(), 1);
+        let manifest = store.resolve(VersionSpec::Pinned(version)).unwrap();
+        assert!(!manifest.files.contains(&stale) && !manifest.checksums.contains_key(&stale));
+        assert_eq!(manifest.files.len(), sealed.files.len() - 1);
+        assert_eq!(manifest.created_at, sealed.created_at);
+    }
+
+}
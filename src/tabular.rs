@@ -0,0 +1,1709 @@
+use std::marker::PhantomData;
+use std::path::Path;
+use std::time::Instant;
+
+use ndarray::{concatenate, Array2, Axis};
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use dendritic::optimizer::prelude::*;
+use dendritic::optimizer::regression::logistic::*;
+
+use crate::artifact::RunMetadata;
+use crate::baseline::score_baselines;
+use crate::config::*;
+use crate::cross_validation::{CrossValidation, FoldResult};
+use crate::dataset::*;
+use crate::error::*;
+use crate::Task;
+use crate::evaluation::{Evaluation, Metrics};
+use crate::export::{class_probabilities, ExportedModel, EXPORTS_DIR, EXPORT_FILE};
+use crate::onnx::OnnxModel;
+use crate::history::{EpochRecord, TrainingHistory};
+use crate::metrics::{ClassificationMetrics, RegressionMetrics};
+use crate::pipeline::{Evaluate, Pipeline, Stages};
+use crate::search::{candidates, SearchResults, Trial};
+use crate::schema::{validate, validate_graph};
+use crate::store::{ArtifactStore, VersionSpec, MANIFEST_FILE, PARAMETERS_FILE};
+use crate::preprocessing::*;
+use crate::split::*;
+
+
+/// Model that can be built and trained by a tabular pipeline
+pub trait TabularModel: Model + ModelSerialize + Sized {
+
+    /// Kind of model as named in experiment configs
+    const KIND: ModelKind;
+
+    /// Construct model for training data.
+    ///
+    /// # Arguments
+    ///
+    /// * `x` - Input features for training.
+    /// * `y` - Target values for training.
+    /// * `config` - Model settings from experiment config.
+    ///
+    fn build(x: &Array2<f64>, y: &Array2<f64>, config: &ModelConfig) -> Result<Self, String>;
+
+    /// Train model on the data it was built with.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Model settings from experiment config.
+    /// * `params` - Batch training arguments.
+    /// * `monitor` - Validation rows to measure loss and stop early on.
+    /// * `rng` - Random source ordering mini-batches.
+    ///
+    fn fit<R: Rng + ?Sized>(
+        &mut self,
+        config: &ModelConfig,
+        params: &HyperparameterConfig,
+        monitor: Option<&Monitor>,
+        rng: &mut R) -> TrainingHistory;
+
+    /// Dimensions of weight parameters
+    fn weight_dim(&self) -> (usize, usize);
+
+    /// Learned weights, one row per feature
+    fn weights(&self) -> Array2<f64> {
+        self.graph().node(1).output()
+    }
+
+    /// Learned bias, one column per output
+    fn bias(&self) -> Array2<f64> {
+        self.graph().node(3).output()
+    }
+
+    /// Overwrite learned weights and bias
+    fn set_parameters(&mut self, weights: Array2<f64>, bias: Array2<f64>) {
+        self.update_parameter(1, weights);
+        self.update_parameter(3, bias);
+    }
+
+    /// Loss of model on rows other than the ones it is trained on
+    fn loss_on(&mut self, x: &Array2<f64>, y: &Array2<f64>) -> f64 {
+        self.set_input(x);
+        self.set_output(y);
+        self.forward();
+        self.loss()
+    }
+
+    /// Linear output `xW + b` before any link function
+    fn decision_function(&self, x: &Array2<f64>) -> Array2<f64> {
+        x.dot(&self.weights()) + &self.bias()
+    }
+
+}
+
+
+impl TabularModel for SGD {
+
+    const KIND: ModelKind = ModelKind::Sgd;
+
+    fn build(x: &Array2<f64>, y: &Array2<f64>, config: &ModelConfig) -> Result<Self, String> {
+        SGD::new(x, y, config.learning_rate)
+    }
+
+    fn fit<R: Rng + ?Sized>(
+        &mut self,
+        config: &ModelConfig,
+        params: &HyperparameterConfig,
+        monitor: Option<&Monitor>,
+        rng: &mut R) -> TrainingHistory {
+
+        match config.optimizer {
+            OptimizerKind::Default => {
+                train_batches(self, params, monitor, rng, |model| model.update_parameters())
+            },
+            OptimizerKind::Adam => {
+                let mut opt = Adam::default(self);
+                train_batches(self, params, monitor, rng, |model| opt.step(model))
+            },
+            OptimizerKind::Nesterov => {
+                let mut opt = Nesterov::default(self);
+                train_batches(self, params, monitor, rng, |model| opt.step(model))
+            },
+            OptimizerKind::Adagrad => {
+                let mut opt = Adagrad::default(self);
+                train_batches(self, params, monitor, rng, |model| opt.step(model))
+            },
+            OptimizerKind::RmsProp => {
+                let mut opt = RMSProp::default(self);
+                train_batches(self, params, monitor, rng, |model| opt.step(model))
+            },
+            OptimizerKind::Adadelta => {
+                let mut opt = Adadelta::default(self);
+                train_batches(self, params, monitor, rng, |model| opt.step(model))
+            }
+        }
+    }
+
+    fn weight_dim(&self) -> (usize, usize) {
+        self.weight_dim
+    }
+
+}
+
+
+impl TabularModel for Logistic {
+
+    const KIND: ModelKind = ModelKind::Logistic;
+
+    fn build(x: &Array2<f64>, y: &Array2<f64>, config: &ModelConfig) -> Result<Self, String> {
+        Logistic::new(x, y, config.multi_class, config.learning_rate)
+    }
+
+    fn fit<R: Rng + ?Sized>(
+        &mut self,
+        _config: &ModelConfig,
+        params: &HyperparameterConfig,
+        monitor: Option<&Monitor>,
+        rng: &mut R) -> TrainingHistory {
+
+        train_batches(self, params, monitor, rng, |model| model.update_parameters())
+    }
+
+    fn weight_dim(&self) -> (usize, usize) {
+        self.weight_dim
+    }
+
+}
+
+
+/// Encoded validation rows watched while training
+pub struct Monitor<'a> {
+
+    /// Validation features, encoded like the training features
+    pub x: &'a Array2<f64>,
+
+    /// Validation targets, encoded like the training targets
+    pub y: &'a Array2<f64>,
+
+    /// When to stop on validation loss, none to train every epoch
+    pub early_stopping: Option<&'a EarlyStoppingConfig>
+}
+
+
+/// Parameters with the lowest validation loss seen so far
+struct BestParameters {
+    epoch: usize,
+    loss: f64,
+    weights: Array2<f64>,
+    bias: Array2<f64>,
+    stale: usize
+}
+
+
+/// Mini-batch training loop ordered by a seeded random source.
+///
+/// Follows dendritic's `train_batch`, which shuffles rows with an unseeded
+/// thread rng, so that batch order is reproducible for a given run seed.
+/// Trailing partial batches are skipped as they are in dendritic. With a
+/// monitor, validation loss is recorded every epoch and, if early stopping
+/// is enabled, training stops once it stops improving and the best
+/// parameters are restored.
+///
+/// # Arguments
+///
+/// * `model` - Model built with the full training data.
+/// * `params` - Batch training arguments.
+/// * `monitor` - Validation rows to measure loss and stop early on.
+/// * `rng` - Random source ordering rows each epoch.
+/// * `step` - Parameter update applied after each backward pass.
+///
+pub fn train_batches<M, R, F>(
+    model: &mut M,
+    params: &HyperparameterConfig,
+    monitor: Option<&Monitor>,
+    rng: &mut R,
+    mut step: F) -> TrainingHistory
+where
+    M: TabularModel,
+    R: Rng + ?Sized,
+    F: FnMut(&mut M) {
+
+    let x_train = model.input();
+    let y_train = model.output();
+    let rows = x_train.nrows();
+    let batch_size = params.batch_size;
+    let total_epochs = params.iterations * params.batch_epochs;
+    let start = Instant::now();
+    let mut history = TrainingHistory::default();
+    let mut best: Option<BestParameters> = None;
+
+    'training: for iteration in 1..=params.iterations {
+
+        for _epoch in 0..params.batch_epochs {
+
+            let mut row_indices: Vec<usize> = (0..rows).collect();
+            row_indices.shuffle(rng);
+
+            let mut batch_losses = Vec::with_capacity(rows / batch_size);
+            for batch in row_indices.chunks_exact(batch_size) {
+                model.set_input(&x_train.select(Axis(0), batch));
+                model.set_output(&y_train.select(Axis(0), batch));
+                model.forward();
+                batch_losses.push(model.loss());
+                model.backward();
+                step(model);
+            }
+
+            let epoch = history.epochs.len() + 1;
+            let validation_loss = monitor.map(|monitor| model.loss_on(monitor.x, monitor.y));
+            history.record(EpochRecord {
+                epoch,
+                iteration,
+                train_loss: batch_losses.iter().sum::<f64>() / batch_losses.len() as f64,
+                validation_loss,
+                elapsed_seconds: start.elapsed().as_secs_f64()
+            });
+
+            let (Some(loss), Some(stopping)) = (validation_loss, monitor.and_then(|monitor| monitor.early_stopping)) else {
+                continue;
+            };
+            if epoch % stopping.eval_every != 0 && epoch != total_epochs {
+                continue;
+            }
+
+            let improved = best.as_ref().is_none_or(|best| loss < best.loss - stopping.min_delta);
+            if improved {
+                best = Some(BestParameters {
+                    epoch,
+                    loss,
+                    weights: model.weights(),
+                    bias: model.bias(),
+                    stale: 0
+                });
+            } else if let Some(best) = &mut best {
+                best.stale += 1;
+                if best.stale >= stopping.patience {
+                    println!(
+                        "Early stopping at epoch {epoch}/{total_epochs}, validation loss last improved at epoch {}",
+                        best.epoch
+                    );
+                    break 'training;
+                }
+            }
+        }
+
+        if let Some(record) = history.epochs.last() {
+            println!("Iteration {}/{} loss: {:?}", iteration, params.iterations, record.train_loss);
+        }
+    }
+
+    if let Some(best) = best {
+        println!("Restoring parameters from epoch {} with validation loss {:?}", best.epoch, best.loss);
+        model.set_parameters(best.weights, best.bias);
+    }
+
+    // Leave the graph on one training batch, as unmonitored training does,
+    // not on the validation rows, since saving writes every node's value
+    if monitor.is_some() {
+        let batch: Vec<usize> = (0..batch_size.min(rows)).collect();
+        model.set_input(&x_train.select(Axis(0), &batch));
+        model.set_output(&y_train.select(Axis(0), &batch));
+        model.forward();
+    }
+
+    history
+}
+
+
+/// Random sources driven by the run seed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RandomStream {
+    Split,
+    Batches,
+    Folds,
+    Search,
+    Baselines
+}
+
+
+/// Seed of the random stream used for one purpose within a run.
+///
+/// Streams are independent so changing the split strategy does not change
+/// mini-batch order and vice versa.
+pub fn stream_seed(seed: u64, stream: RandomStream) -> u64 {
+    seed.wrapping_add(stream as u64)
+}
+
+
+/// Generic pipeline for parquet or CSV backed tabular datasets.
+///
+/// The model type is fixed by `M` while the feature spec (dataset, feature
+/// and target columns) and transform chain come from an experiment config.
+pub struct TabularPipeline<M: TabularModel> {
+
+    /// Config describing dataset, transforms, model and training
+    config: ExperimentConfig,
+
+    /// Features of every row as ndarray
+    x: Array2<f64>,
+
+    /// Targets of every row as ndarray
+    y: Array2<f64>,
+
+    /// Group id of every row of the main dataset file (grouped splits only)
+    groups: Option<Array2<f64>>,
+
+    /// Rows of `x` and `y` in each partition
+    indices: SplitIndices,
+
+    /// Training features as ndarray
+    x_train: Array2<f64>,
+
+    /// Training targets as ndarray
+    y_train: Array2<f64>,
+
+    /// Validation features as ndarray
+    x_val: Array2<f64>,
+
+    /// Validation targets as ndarray
+    y_val: Array2<f64>,
+
+    /// Testing features as ndarray
+    x_test: Array2<f64>,
+
+    /// Testing targets as ndarray
+    y_test: Array2<f64>,
+
+    /// Fitted encoders and column names saved with the model
+    preprocessing: Preprocessing,
+
+    /// Model type trained by pipeline
+    model: PhantomData<M>
+}
+
+
+impl<M: TabularModel> TabularPipeline<M> {
+
+    /// Create pipeline from experiment config, which must declare the model type `M`
+    pub fn from_config(config: ExperimentConfig) -> PipelineResult<Self> {
+
+        if config.model.kind != M::KIND {
+            return Err(PipelineError::Config(format!(
+                "experiment {:?} configures a {:?} model, pipeline expects {:?}",
+                config.name, config.model.kind, M::KIND
+            )));
+        }
+
+        // Column names are filled in once the load stage has read them
+        let preprocessing = Preprocessing {
+            features: Vec::new(),
+            target: String::new(),
+            x_encode: FittedEncoder::None,
+            y_encode: FittedEncoder::None,
+            feature_stdev: Vec::new(),
+            class_names: Vec::new()
+        };
+
+        Ok(TabularPipeline {
+            config,
+            x: Array2::zeros((0, 0)),
+            y: Array2::zeros((0, 0)),
+            groups: None,
+            indices: SplitIndices::default(),
+            x_train: Array2::zeros((0, 0)),
+            y_train: Array2::zeros((0, 0)),
+            x_val: Array2::zeros((0, 0)),
+            y_val: Array2::zeros((0, 0)),
+            x_test: Array2::zeros((0, 0)),
+            y_test: Array2::zeros((0, 0)),
+            preprocessing,
+            model: PhantomData
+        })
+    }
+
+    /// Create pipeline by name.
+    ///
+    /// Registry pipelines use their built in config, any other name is
+    /// looked up in the experiments directory.
+    pub fn try_register(name: &str) -> PipelineResult<Self> {
+        let config = match crate::lookup(name) {
+            Some(entry) => entry.config(),
+            None => ExperimentConfig::from_file(ExperimentConfig::path_for(name)).map_err(PipelineError::Config)?
+        };
+        Self::from_config(config)
+    }
+
+    /// Config the pipeline runs with
+    pub fn config(&self) -> &ExperimentConfig {
+        &self.config
+    }
+
+    /// Row indices of the train, validation and test partitions
+    pub fn indices(&self) -> &SplitIndices {
+        &self.indices
+    }
+
+    /// Metadata describing the current run
+    pub fn metadata(&self) -> RunMetadata {
+        RunMetadata {
+            experiment: self.config.name.clone(),
+            seed: self.config.seed,
+            split: self.config.dataset.split,
+            train_rows: self.indices.train.len(),
+            validation_rows: self.indices.validation.len(),
+            test_rows: self.indices.test.len(),
+            model: self.config.model.clone(),
+            hyperparameters: self.config.hyperparameters.clone()
+        }
+    }
+
+    /// Versioned artifact store the pipeline saves models to
+    pub fn store(&self) -> ArtifactStore {
+        ArtifactStore::new(&self.config.output_dir)
+    }
+
+    /// Build and train a model on encoded rows with the configured settings.
+    ///
+    /// Loss on encoded validation rows, if there are any, is recorded every
+    /// epoch and used for early stopping when the config enables it.
+    fn fit_model(
+        &self,
+        x: &Array2<f64>,
+        y: &Array2<f64>,
+        x_val: &Array2<f64>,
+        y_val: &Array2<f64>) -> PipelineResult<(M, TrainingHistory)> {
+
+
+        let batch_size = self.config.hyperparameters.batch_size;
+        if batch_size > x.nrows() {
+            return Err(PipelineError::ShapeMismatch {
+                context: format!("{} training batches", self.config.name),
+                expected: format!("batch size of at most {} rows", x.nrows()),
+                found: format!("batch size of {batch_size}")
+            });
+        }
+
+        let mut model = M::build(x, y, &self.config.model)
+            .map_err(PipelineError::ModelConstruction)?;
+
+        let monitor = (x_val.nrows() > 0).then_some(Monitor {
+            x: x_val,
+            y: y_val,
+            early_stopping: self.config.early_stopping.as_ref()
+        });
+
+        let mut rng = StdRng::seed_from_u64(stream_seed(self.config.seed, RandomStream::Batches));
+        let history = model.fit(&self.config.model, &self.config.hyperparameters, monitor.as_ref(), &mut rng);
+        Ok((model, history))
+    }
+
+    /// Fit encoders and model on raw rows, keeping the result in memory.
+    ///
+    /// Validation rows are only used to monitor loss and stop early.
+    fn fit_rows(&self, rows: &[usize], validation: &[usize]) -> PipelineResult<SavedModel<M>> {
+
+        let (x, y) = (self.x.select(Axis(0), rows), self.y.select(Axis(0), rows));
+        let transforms = &self.config.transforms;
+        let preprocessing = Preprocessing {
+            x_encode: FittedEncoder::fit(transforms.features, &x.view()),
+            y_encode: FittedEncoder::fit(transforms.target, &y.view()),
+            feature_stdev: Preprocessing::column_stdev(&x.view()),
+            ..self.preprocessing.clone()
+        };
+
+        let x_enc = preprocessing.x_encode.transform(&x.view())?;
+        let y_enc = preprocessing.y_encode.transform(&y.view())?;
+        let x_val = preprocessing.x_encode.transform(&self.x.select(Axis(0), validation).view())?;
+        let y_val = preprocessing.y_encode.transform(&self.y.select(Axis(0), validation).view())?;
+        let (model, _) = self.fit_model(&x_enc, &y_enc, &x_val, &y_val)?;
+        Ok(SavedModel { model, preprocessing })
+    }
+
+    /// Run (repeated) k-fold cross validation over the non test rows.
+    ///
+    /// Encoders and model are refit from scratch for every fold so no
+    /// statistics leak from a fold's validation rows. Folds follow the
+    /// configured split strategy, and are stratified for classifiers. Fold
+    /// models train for the full epoch count without early stopping.
+    ///
+    /// # Arguments
+    ///
+    /// * `folds` - Number of folds per repeat.
+    /// * `repeats` - Number of times folds are redrawn.
+    ///
+    pub fn cross_validate(&self, folds: usize, repeats: usize) -> PipelineResult<CrossValidation> {
+
+        if self.x.nrows() == 0 {
+            return Err(PipelineError::StageOrder("cross validation requires load".to_string()));
+        }
+
+        let task = self.config.task();
+        let strategy = match (self.config.dataset.split, task) {
+            (SplitKind::Grouped, _) => SplitKind::Grouped,
+            (_, Task::Binary | Task::Multiclass) => SplitKind::Stratified,
+            (split, Task::Regression) => split
+        };
+
+        let pool: Vec<usize> = self.indices.train
+            .iter()
+            .chain(&self.indices.validation)
+            .copied()
+            .collect();
+
+        let mut rng = StdRng::seed_from_u64(stream_seed(self.config.seed, RandomStream::Folds));
+        let mut results = Vec::new();
+        for repeat in 0..repeats {
+            let assigned = k_fold(
+                strategy,
+                &pool,
+                folds,
+                Some(self.y.column(0)),
+                self.groups.as_ref().map(|groups| groups.column(0)),
+                &mut rng
+            )?;
+
+            for (fold, Fold { train, validation }) in assigned.into_iter().enumerate() {
+                println!("Cross validation repeat {}/{repeats}, fold {}/{folds}", repeat + 1, fold + 1);
+                let mut fitted = self.fit_rows(&train, &[])?;
+                let metrics = fitted.score(
+                    task,
+                    &self.x.select(Axis(0), &validation),
+                    &self.y.select(Axis(0), &validation)
+                )?;
+                results.push(FoldResult {
+                    repeat,
+                    fold,
+                    train_rows: train.len(),
+                    validation_rows: validation.len(),
+                    metrics
+                });
+            }
+        }
+
+        Ok(CrossValidation {
+            experiment: self.config.name.clone(),
+            task,
+            strategy,
+            folds,
+            repeats,
+            seed: self.config.seed,
+            summary: CrossValidation::summarize(&results),
+            results
+        })
+    }
+
+    /// Search hyperparameters and save the best model.
+    ///
+    /// Candidates come from the config's search space and are scored on the
+    /// validation partition or by cross validation. Candidates scored on the
+    /// validation partition stop early on a slice of the training rows, so
+    /// the rows they are ranked on are never used to fit them. Candidates
+    /// that fail to train are recorded with their error. The best candidate
+    /// is retrained on the training partition and saved to the artifact
+    /// directory.
+    pub fn search(&mut self) -> PipelineResult<SearchResults> {
+
+        if self.x.nrows() == 0 {
+            return Err(PipelineError::StageOrder("search requires load".to_string()));
+        }
+
+        let base = self.config.clone();
+        let (selection, metric) = (base.search.selection, base.search_metric());
+        if selection == Selection::Validation && self.indices.validation.is_empty() {
+            return Err(PipelineError::ShapeMismatch {
+                context: format!("{} search validation split", base.name),
+                expected: "at least 1 validation row (set dataset.validation_size)".to_string(),
+                found: "0 rows".to_string()
+            });
+        }
+
+        let mut rng = StdRng::seed_from_u64(stream_seed(base.seed, RandomStream::Search));
+        let mut trials = Vec::new();
+        for candidate in candidates(&base, &mut rng) {
+            println!("Search candidate {:?}", candidate);
+            self.config = candidate.apply(&base);
+            let outcome = self.config
+                .validate()
+                .map_err(PipelineError::Config)
+                .and_then(|_| self.score_candidate(selection, &metric));
+            let (score, std, error) = match outcome {
+                Ok((score, std)) => (Some(score), std, None),
+                Err(err) => (None, None, Some(err.to_string()))
+            };
+            trials.push(Trial { rank: None, candidate, score, std, error });
+        }
+        self.config = base;
+
+        let mut results = SearchResults {
+            experiment: self.config.name.clone(),
+            strategy: self.config.search.strategy,
+            selection,
+            metric,
+            seed: self.config.seed,
+            trials
+        };
+        results.rank();
+
+        if let Some(best) = results.best() {
+            self.config = best.candidate.apply(&self.config);
+            self.try_transform()?;
+            self.try_train()?;
+        }
+
+        results.save(&self.config.output_dir)?;
+        Ok(results)
+    }
+
+    /// Score the current config on validation rows or by cross validation
+    fn score_candidate(&self, selection: Selection, metric: &str) -> PipelineResult<(f64, Option<f64>)> {
+
+        let undefined = || PipelineError::Config(format!("metric {metric:?} is undefined for the data"));
+        match selection {
+            Selection::Validation => {
+                let validation = &self.indices.validation;
+                let (train, monitor) = self.early_stopping_rows()?;
+                let mut fitted = self.fit_rows(train, monitor)?;
+                let metrics = fitted.score(
+                    self.config.task(),
+                    &self.x.select(Axis(0), validation),
+                    &self.y.select(Axis(0), validation)
+                )?;
+                Ok((metrics.get(metric).ok_or_else(undefined)?, None))
+            },
+            Selection::CrossValidation => {
+                let settings = &self.config.cross_validation;
+                let results = self.cross_validate(settings.folds, settings.repeats)?;
+                let summary = results.metric(metric).ok_or_else(undefined)?;
+                Ok((summary.mean, Some(summary.std)))
+            }
+        }
+    }
+
+    /// Training rows a search candidate is fit on and the rows it stops early on.
+    ///
+    /// Candidates are ranked on the validation partition, so with early
+    /// stopping enabled the trailing `validation_size` fraction of the
+    /// training rows is held out to watch instead. Without early stopping
+    /// every training row is fit.
+    fn early_stopping_rows(&self) -> PipelineResult<(&[usize], &[usize])> {
+
+        let train = &self.indices.train;
+        if self.config.early_stopping.is_none() {
+            return Ok((train, &[]));
+        }
+
+        if train.len() < 2 {
+            return Err(PipelineError::ShapeMismatch {
+                context: format!("{} search early stopping rows", self.config.name),
+                expected: "at least 2 training rows".to_string(),
+                found: format!("{} rows", train.len())
+            });
+        }
+        let held_out = (train.len() as f64 * self.config.dataset.validation_size).ceil() as usize;
+        Ok(train.split_at(train.len() - held_out.clamp(1, train.len() - 1)))
+    }
+
+    fn read_frame(&self, path: &str) -> PipelineResult<Frame> {
+        let dataset = &self.config.dataset;
+        let df = read_table(path)?;
+        let x = select_array(&df, path, &dataset.features)?;
+        let y = select_array(&df, path, std::slice::from_ref(&dataset.target))?;
+        let groups = match &dataset.group {
+            Some(group) => Some(select_array(&df, path, std::slice::from_ref(group))?),
+            None => None
+        };
+        let class_names = match &dataset.labels {
+            Some(labels) => class_names(&df, path, &y.column(0), labels)?,
+            None => Vec::new()
+        };
+        Ok(Frame { x, y, groups, class_names })
+    }
+
+}
+
+
+/// Columns selected from a single dataset file
+struct Frame {
+    x: Array2<f64>,
+    y: Array2<f64>,
+    groups: Option<Array2<f64>>,
+    class_names: Vec<String>
+}
+
+
+/// Load saved model from artifact directory.
+///
+/// Checks that the parameter file and the computation graph it points to
+/// exist and agree before handing off to dendritic, which panics on a
+/// missing graph and loads mismatched ones.
+pub fn load_model<M: TabularModel>(dir: &str) -> PipelineResult<M> {
+
+    let parameter_path = format!("{dir}/{PARAMETERS_FILE}");
+    if !Path::new(&parameter_path).is_file() {
+        return Err(PipelineError::MissingFile { path: parameter_path });
+    }
+
+    validate_graph(dir).into_result()?;
+    M::load(dir).map_err(|err| PipelineError::artifact(dir, err))
+}
+
+
+/// Trained model loaded together with the preprocessing it was trained with
+pub struct SavedModel<M: TabularModel> {
+
+    /// Model parameters loaded from disk
+    pub model: M,
+
+    /// Fitted encoders and column names
+    pub preprocessing: Preprocessing
+}
+
+
+impl<M: TabularModel> SavedModel<M> {
+
+    /// Load model and preprocessing state from artifact directory
+    pub fn load(dir: &str) -> PipelineResult<Self> {
+
+        let model: M = load_model(dir)?;
+        let preprocessing = Preprocessing::load(dir)?;
+
+        let features = preprocessing.features.len();
+        if model.weight_dim().0 != features {
+            return Err(PipelineError::ShapeMismatch {
+                context: format!("{dir} saved model weights"),
+                expected: format!("{features} feature rows"),
+                found: format!("{:?}", model.weight_dim())
+            });
+        }
+
+        Ok(SavedModel { model, preprocessing })
+    }
+
+    /// Predict on raw (untransformed) feature rows.
+    ///
+    /// Features are encoded with the training time encoder and scaled
+    /// regression outputs are mapped back to the original target scale.
+    pub fn predict(&mut self, x: &Array2<f64>) -> PipelineResult<Array2<f64>> {
+
+        let encoded = self.preprocessing.x_encode.transform(&x.view())?;
+        let predictions = self.model.predict(&encoded);
+
+        match self.preprocessing.y_encode.is_scaler() {
+            true => self.preprocessing.y_encode.inverse_transform(&predictions.view()),
+            false => Ok(predictions)
+        }
+    }
+
+    /// Class probabilities for raw (untransformed) feature rows.
+    ///
+    /// Binary models return the positive class probability as a single
+    /// column, multi class models one softmax column per class.
+    pub fn probabilities(&self, x: &Array2<f64>) -> PipelineResult<Array2<f64>> {
+
+        if M::KIND != ModelKind::Logistic {
+            return Err(PipelineError::Config(format!(
+                "{:?} models do not predict class probabilities", M::KIND
+            )));
+        }
+
+        let encoded = self.preprocessing.x_encode.transform(&x.view())?;
+        Ok(class_probabilities(self.model.decision_function(&encoded)))
+    }
+
+    /// Compact inference-only export of the model
+    pub fn export(&self, task: Task) -> ExportedModel {
+        ExportedModel::from_model(&self.model, &self.preprocessing, task)
+    }
+
+    /// Compute task metrics on raw (untransformed) rows.
+    ///
+    /// # Arguments
+    ///
+    /// * `task` - Prediction task of the model.
+    /// * `x` - Raw feature rows.
+    /// * `y` - Raw targets (class labels for classification).
+    ///
+    pub fn score(&mut self, task: Task, x: &Array2<f64>, y: &Array2<f64>) -> PipelineResult<Metrics> {
+        match task {
+            Task::Regression => {
+                let predictions = self.predict(x)?;
+                Ok(Metrics::Regression(RegressionMetrics::compute(&y.column(0), &predictions.column(0))))
+            },
+            Task::Binary | Task::Multiclass => {
+                let probabilities = self.probabilities(x)?;
+                let labels: Vec<usize> = y.column(0).iter().map(|&label| label as usize).collect();
+                Ok(Metrics::Classification(ClassificationMetrics::compute(&labels, &probabilities.view())))
+            }
+        }
+    }
+
+}
+
+
+/// Build pipeline for experiment config with the model type it declares
+pub fn build(config: ExperimentConfig) -> PipelineResult<Box<dyn Pipeline>> {
+    Ok(match config.model.kind {
+        ModelKind::Sgd => Box::new(TabularPipeline::<SGD>::from_config(config)?),
+        ModelKind::Logistic => Box::new(TabularPipeline::<Logistic>::from_config(config)?)
+    })
+}
+
+
+/// Compact export of the model saved in a version directory.
+///
+/// # Arguments
+///
+/// * `dir` - Version directory holding the model and its preprocessing.
+/// * `kind` - Kind of model saved.
+/// * `task` - Prediction task of the model.
+///
+pub fn export_version(dir: &str, kind: ModelKind, task: Task) -> PipelineResult<ExportedModel> {
+
+    fn run<M: TabularModel>(dir: &str, task: Task) -> PipelineResult<ExportedModel> {
+        Ok(SavedModel::<M>::load(dir)?.export(task))
+    }
+
+    match kind {
+        ModelKind::Sgd => run::<SGD>(dir, task),
+        ModelKind::Logistic => run::<Logistic>(dir, task)
+    }
+}
+
+
+/// Load the compact export of the saved version an experiment config names.
+///
+/// The version is validated against the artifact schema first, printing
+/// any warnings. Versions without a `model.json` are exported from their
+/// saved graph.
+///
+/// Returns the version number loaded and its export.
+pub fn load_export(config: &ExperimentConfig) -> PipelineResult<(u32, ExportedModel)> {
+
+    let store = ArtifactStore::new(&config.output_dir);
+    let version = store.resolve(config.model_version)?.version;
+    let dir = store.version_dir(version);
+    println!("Using model version {dir} ({})", config.model_version);
+    for issue in validate(&dir).into_result()?.issues {
+        println!("{issue}");
+    }
+
+    let exported = match Path::new(&dir).join(EXPORT_FILE).is_file() {
+        true => ExportedModel::load(&dir)?,
+        false => export_version(&dir, config.model.kind, config.task())?
+    };
+    Ok((version, exported))
+}
+
+
+/// Export the saved version an experiment config names as a compact inference-only
+/// model and as an ONNX graph.
+///
+/// Sealed version directories are never written to, since new files
+/// would break their manifest. The version's own `model.json` is copied
+/// when it has one, so the export matches the training time weights.
+///
+/// # Arguments
+///
+/// * `config` - Experiment whose `model_version` is exported.
+/// * `output` - Directory to write the export to, `exports/<pipeline>/v<n>` when `None`.
+///
+/// Returns the directory the export was written to.
+pub fn export(config: &ExperimentConfig, output: Option<&str>) -> PipelineResult<String> {
+
+    let (version, exported) = load_export(config)?;
+    let output = match output {
+        Some(output) => output.to_string(),
+        None => format!("{EXPORTS_DIR}/{}/{}", config.name, VersionSpec::Pinned(version))
+    };
+    if Path::new(&output).join(MANIFEST_FILE).is_file() {
+        return Err(PipelineError::Config(format!(
+            "{output} holds a saved model version, export to another directory"
+        )));
+    }
+
+    let output = output.as_str();
+    std::fs::create_dir_all(output).map_err(|err| PipelineError::artifact(output, err))?;
+    exported.save(output)?;
+    OnnxModel::from_export(&exported)?.save(output)?;
+    Ok(output.to_string())
+}
+
+
+/// Load dataset and cross validate the model type an experiment config declares
+pub fn cross_validate(config: ExperimentConfig) -> PipelineResult<CrossValidation> {
+
+    fn run<M: TabularModel>(config: ExperimentConfig) -> PipelineResult<CrossValidation> {
+        let settings = config.cross_validation.clone();
+        let mut pipeline = TabularPipeline::<M>::from_config(config)?;
+        pipeline.try_load()?;
+        pipeline.cross_validate(settings.folds, settings.repeats)
+    }
+
+    match config.model.kind {
+        ModelKind::Sgd => run::<SGD>(config),
+        ModelKind::Logistic => run::<Logistic>(config)
+    }
+}
+
+
+/// Load dataset and run the hyperparameter search an experiment config declares
+pub fn search(config: ExperimentConfig) -> PipelineResult<SearchResults> {
+
+    fn run<M: TabularModel>(config: ExperimentConfig) -> PipelineResult<SearchResults> {
+        let mut pipeline = TabularPipeline::<M>::from_config(config)?;
+        pipeline.try_load()?;
+        pipeline.search()
+    }
+
+    match config.model.kind {
+        ModelKind::Sgd => run::<SGD>(config),
+        ModelKind::Logistic => run::<Logistic>(config)
+    }
+}
+
+
+/// Build pipeline from experiment config file
+pub fn from_file(path: &str) -> PipelineResult<Box<dyn Pipeline>> {
+    let config = ExperimentConfig::from_file(path).map_err(PipelineError::Config)?;
+    build(config)
+}
+
+
+impl<M: TabularModel> ModelPipeline for TabularPipeline<M> {
+
+    /// See `try_register`, panics if the pipeline cannot be created
+    fn register(name: &str) -> Self {
+        expect(Self::try_register(name))
+    }
+
+}
+
+
+impl<M: TabularModel> Stages for TabularPipeline<M> {
+
+    fn try_load(&mut self) -> PipelineResult<()> {
+
+        println!("Running load step for: {:?}", self.config.name);
+
+        let dataset = &self.config.dataset;
+        let Frame { mut x, mut y, groups, class_names } = self.read_frame(&dataset.path)?;
+
+        // A separate test file replaces the held out test fraction
+        let sizes = SplitSizes {
+            validation: dataset.validation_size,
+            test: if dataset.test_path.is_some() { 0.0 } else { dataset.test_size }
+        };
+
+        let mut indices = split_indices(
+            dataset.split,
+            x.nrows(),
+            sizes,
+            Some(y.column(0)),
+            groups.as_ref().map(|groups| groups.column(0)),
+            &mut StdRng::seed_from_u64(stream_seed(self.config.seed, RandomStream::Split))
+        )?;
+
+        if let Some(test_path) = &dataset.test_path {
+            let test = self.read_frame(test_path)?;
+            indices.test = (x.nrows()..x.nrows() + test.x.nrows()).collect();
+            x = concatenate![Axis(0), x, test.x];
+            y = concatenate![Axis(0), y, test.y];
+        }
+
+        if indices.train.is_empty() {
+            return Err(PipelineError::ShapeMismatch {
+                context: format!("{} training split", self.config.name),
+                expected: "at least 1 training row".to_string(),
+                found: "0 rows".to_string()
+            });
+        }
+
+        self.x = x;
+        self.y = y;
+        self.groups = groups;
+        self.indices = indices;
+        self.preprocessing.features = dataset.features.clone();
+        self.preprocessing.target = dataset.target.clone();
+        self.preprocessing.class_names = class_names;
+
+        println!("X features shape: {:?}", self.x.shape());
+        println!("Y target shape: {:?}", self.y.shape());
+        println!(
+            "Split rows (train, validation, test): ({}, {}, {})",
+            self.indices.train.len(),
+            self.indices.validation.len(),
+            self.indices.test.len()
+        );
+        Ok(())
+
+    }
+
+    fn try_transform(&mut self) -> PipelineResult<()> {
+
+        println!("Running transform step for: {:?}", self.config.name);
+
+        if self.x.nrows() == 0 {
+            return Err(PipelineError::StageOrder("transform requires load".to_string()));
+        }
+
+        if self.x.nrows() != self.y.nrows() {
+            return Err(PipelineError::ShapeMismatch {
+                context: format!("{} features and target", self.config.name),
+                expected: format!("{} target rows", self.x.nrows()),
+                found: format!("{} target rows", self.y.nrows())
+            });
+        }
+
+        // Split before fitting so encoders only ever see training rows
+        let split = self.indices.clone().apply(&self.x, &self.y);
+
+        let transforms = &self.config.transforms;
+        let x_encode = FittedEncoder::fit(transforms.features, &split.x.train.view());
+        let y_encode = FittedEncoder::fit(transforms.target, &split.y.train.view());
+
+        self.x_train = x_encode.transform(&split.x.train.view())?;
+        self.y_train = y_encode.transform(&split.y.train.view())?;
+        self.x_val = x_encode.transform(&split.x.validation.view())?;
+        self.y_val = y_encode.transform(&split.y.validation.view())?;
+        self.x_test = x_encode.transform(&split.x.test.view())?;
+        self.y_test = y_encode.transform(&split.y.test.view())?;
+        self.preprocessing.x_encode = x_encode;
+        self.preprocessing.y_encode = y_encode;
+        self.preprocessing.feature_stdev = Preprocessing::column_stdev(&split.x.train.view());
+
+        println!("X Shapes: {:?}, {:?}, {:?}", self.x_train.shape(), self.x_val.shape(), self.x_test.shape());
+        println!("Y Shapes: {:?}, {:?}, {:?}", self.y_train.shape(), self.y_val.shape(), self.y_test.shape());
+        Ok(())
+
+    }
+
+    fn try_train(&mut self) -> PipelineResult<()> {
+
+        println!("Running train step for: {:?}", self.config.name);
+
+        if self.x_train.nrows() == 0 {
+            return Err(PipelineError::StageOrder("train requires transform".to_string()));
+        }
+
+        let (mut model, history) = self.fit_model(&self.x_train, &self.y_train, &self.x_val, &self.y_val)?;
+
+        let store = self.store();
+        let (version, dir) = store.create()?;
+        model.save(&dir).map_err(|err| PipelineError::artifact(&dir, err))?;
+        history.save(&dir)?;
+        self.preprocessing.save(&dir)?;
+        self.metadata().save(&dir)?;
+        ExportedModel::from_model(&model, &self.preprocessing, self.config.task()).save(&dir)?;
+        store.commit(version, &self.config)?;
+
+        println!("Model loss after training: {:?}", model.loss());
+        println!("Saved model version {dir}");
+        Ok(())
+
+    }
+
+    fn try_evaluate(&mut self) -> PipelineResult<()> {
+
+        println!("Running evaluate step for: {:?}", self.config.name);
+
+        if self.x.nrows() == 0 {
+            return Err(PipelineError::StageOrder("evaluate requires load".to_string()));
+        }
+
+        if self.indices.test.is_empty() {
+            return Err(PipelineError::ShapeMismatch {
+                context: format!("{} evaluation split", self.config.name),
+                expected: "at least 1 test row".to_string(),
+                found: "0 rows".to_string()
+            });
+        }
+
+        // Raw held out rows, encoded by the preprocessing saved with the model
+        let x_test = self.x.select(Axis(0), &self.indices.test);
+        let y_test = self.y.select(Axis(0), &self.indices.test);
+
+        let dir = self.store().dir(self.config.model_version)?;
+        println!("Using model version {dir} ({})", self.config.model_version);
+        for issue in validate(&dir).into_result()?.issues {
+            println!("{issue}");
+        }
+        let mut saved: SavedModel<M> = SavedModel::load(&dir)?;
+        let task = self.config.task();
+        let metrics = saved.score(task, &x_test, &y_test)?;
+
+        let mut rng = StdRng::seed_from_u64(stream_seed(self.config.seed, RandomStream::Baselines));
+        let baselines = score_baselines(
+            &self.config,
+            &self.y.column(0),
+            &self.indices.train,
+            &self.indices.test,
+            &mut rng
+        );
+
+        let evaluation = Evaluation {
+            experiment: self.config.name.clone(),
+            task,
+            partition: "test".to_string(),
+            rows: self.indices.test.len(),
+            seed: self.config.seed,
+            metrics,
+            baselines
+        };
+
+        evaluation.save(&dir)?;
+        println!("{}", evaluation.to_markdown());
+        Ok(())
+
+    }
+
+    fn try_inference(&mut self) -> PipelineResult<()> {
+
+        println!("Running inference step for: {:?}", self.config.name);
+
+        if self.x.nrows() == 0 {
+            return Err(PipelineError::StageOrder("inference requires load".to_string()));
+        }
+
+        // Without held out rows predictions are made on the training rows
+        let rows = match self.indices.test.is_empty() {
+            true => &self.indices.train,
+            false => &self.indices.test
+        };
+
+        // Raw rows are used so the saved preprocessing is exercised end to end
+        let sample = &rows[..rows.len().min(5)];
+        let sample_data = self.x.select(Axis(0), sample);
+        let actual = self.y.select(Axis(0), sample);
+
+        let dir = self.store().dir(self.config.model_version)?;
+        println!("Using model version {dir} ({})", self.config.model_version);
+        for issue in validate(&dir).into_result()?.issues {
+            println!("{issue}");
+        }
+        let mut saved: SavedModel<M> = SavedModel::load(&dir)?;
+        let predictions = saved.predict(&sample_data)?;
+
+        println!("Predictions");
+        println!("{:?}", predictions);
+
+        println!("Actual");
+        println!("{:?}", actual);
+        Ok(())
+
+    }
+
+}
+
+
+/// Unwrap a fallible stage for dendritic's infallible pipeline traits
+fn expect<T>(result: PipelineResult<T>) -> T {
+    result.unwrap_or_else(|err| panic!("{err}"))
+}
+
+
+impl<M: TabularModel> Load for TabularPipeline<M> {
+
+    fn load(&mut self) {
+        expect(self.try_load());
+    }
+
+}
+
+
+impl<M: TabularModel> Transform for TabularPipeline<M> {
+
+    fn transform(&mut self) {
+        expect(self.try_transform());
+    }
+
+}
+
+
+impl<M: TabularModel> Evaluate for TabularPipeline<M> {
+
+    fn evaluate(&mut self) {
+        expect(self.try_evaluate());
+    }
+
+}
+
+
+impl<M: TabularModel> Train for TabularPipeline<M> {
+
+    fn train(&mut self) {
+        expect(self.try_train());
+    }
+
+}
+
+
+impl<M: TabularModel> Inference for TabularPipeline<M> {
+
+    fn inference(&mut self) {
+        expect(self.try_inference());
+    }
+
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use polars::prelude::*;
+
+    fn write_dataset(name: &str, x: &[f64], y: &[f64]) -> String {
+        let dir = std::env::temp_dir().join("crate_tabular_tests");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{name}.parquet"));
+        let mut df = df!("x" => x, "y" => y).unwrap();
+        let mut file = std::fs::File::create(&path).unwrap();
+        ParquetWriter::new(&mut file).finish(&mut df).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn transform(path: &str) -> TabularPipeline<SGD> {
+        let source = format!(r#"
+            name = "leakage"
+            output_dir = "models/leakage"
+
+            [dataset]
+            path = "{path}"
+            features = ["x"]
+            target = "y"
+            test_size = 0.25
+
+            [transforms]
+            features = "standard"
+            target = "min_max"
+
+            [model]
+            kind = "sgd"
+            learning_rate = 0.01
+        "#);
+        let config = ExperimentConfig::parse(&source).unwrap();
+        let mut pipeline = TabularPipeline::<SGD>::from_config(config).unwrap();
+        pipeline.try_load().unwrap();
+        pipeline.try_transform().unwrap();
+        pipeline
+    }
+
+    #[test]
+    fn config_errors_do_not_panic() {
+        assert!(TabularPipeline::<Logistic>::from_config(crate::iris::config()).is_ok());
+
+        let err = TabularPipeline::<SGD>::from_config(crate::iris::config()).err().unwrap();
+        assert_eq!(err.kind(), "config");
+        assert!(err.to_string().contains("Logistic model, pipeline expects Sgd"), "{err}");
+
+        let err = crate::lookup("iris").unwrap().build_from(crate::coca_cola_stock::config()).err().unwrap();
+        assert_eq!(err.kind(), "config");
+
+        let err = TabularPipeline::<SGD>::try_register("no_such_experiment").err().unwrap();
+        assert_eq!(err.kind(), "config");
+        assert!(err.to_string().contains("no_such_experiment.toml"), "{err}");
+    }
+
+    #[test]
+    fn encoders_ignore_test_rows() {
+        let train = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let baseline = write_dataset(
+            "baseline",
+            &[&train[..], &[7.0, 8.0]].concat(),
+            &[&train[..], &[7.0, 8.0]].concat()
+        );
+        let outliers = write_dataset(
+            "outliers",
+            &[&train[..], &[1.0e6, -1.0e6]].concat(),
+            &[&train[..], &[5.0e5, -5.0e5]].concat()
+        );
+
+        let baseline = transform(&baseline);
+        let outliers = transform(&outliers);
+
+        assert_eq!(baseline.preprocessing, outliers.preprocessing);
+        assert_eq!(baseline.x_train, outliers.x_train);
+
+        match &baseline.preprocessing.x_encode {
+            FittedEncoder::Standard { mean, .. } => assert_eq!(mean, &vec![3.5]),
+            other => panic!("expected standard encoder, found {other:?}")
+        }
+        match &baseline.preprocessing.y_encode {
+            FittedEncoder::MinMax { min, max } => assert_eq!((min[0], max[0]), (1.0, 6.0)),
+            other => panic!("expected min max encoder, found {other:?}")
+        }
+
+        // Held out rows are encoded with the frozen training statistics
+        assert_eq!(outliers.y_test[[0, 0]], (5.0e5 - 1.0) / 5.0);
+    }
+
+    #[test]
+    fn search_stops_early_on_training_rows() {
+        let x: Vec<f64> = (0..40).map(f64::from).collect();
+        let path = write_dataset("search_rows", &x, &x);
+        let source = format!(r#"
+            name = "search_rows"
+            output_dir = "models/search_rows"
+
+            [dataset]
+            path = "{path}"
+            features = ["x"]
+            target = "y"
+            test_size = 0.2
+            validation_size = 0.2
+
+            [model]
+            kind = "sgd"
+            learning_rate = 0.01
+
+            [early_stopping]
+            patience = 2
+        "#);
+        let mut pipeline = TabularPipeline::<SGD>::from_config(ExperimentConfig::parse(&source).unwrap()).unwrap();
+        pipeline.try_load().unwrap();
+
+        let (train, monitor) = pipeline.early_stopping_rows().unwrap();
+        assert_eq!((train.len(), monitor.len()), (19, 5));
+        assert_eq!([train, monitor].concat(), pipeline.indices.train);
+        assert!(monitor.iter().all(|row| !pipeline.indices.validation.contains(row)));
+
+        pipeline.config.early_stopping = None;
+        let (train, monitor) = pipeline.early_stopping_rows().unwrap();
+        assert_eq!((train, monitor.len()), (pipeline.indices.train.as_slice(), 0));
+    }
+
+    /// Rows of `y = 2x + 1` on `[0, 1)`, with `sign` flipping the target
+    fn line(sign: f64) -> (Array2<f64>, Array2<f64>) {
+        let x = Array2::from_shape_fn((32, 1), |(row, _)| row as f64 / 32.0);
+        let y = x.mapv(|x| sign * (2.0 * x + 1.0));
+        (x, y)
+    }
+
+    /// Train SGD on `line(1.0)` with a seeded batch order, watching `monitor` if given
+    fn fit_line(epochs: usize, monitor: Option<&Monitor>) -> (SGD, TrainingHistory) {
+        let (x, y) = line(1.0);
+        let config = ModelConfig { kind: ModelKind::Sgd, multi_class: false, learning_rate: 0.01, optimizer: OptimizerKind::Default };
+        let params = HyperparameterConfig { iterations: 1, batch_size: 8, batch_epochs: epochs };
+        let mut model = SGD::build(&x, &y, &config).unwrap();
+        let history = train_batches(&mut model, &params, monitor, &mut StdRng::seed_from_u64(1), |model| model.update_parameters());
+        (model, history)
+    }
+
+    #[test]
+    fn early_stopping_restores_best_parameters() {
+        // Validation targets oppose the training targets, so their loss is lowest after the first epoch
+        let (x_val, y_val) = line(-1.0);
+        let stopping = EarlyStoppingConfig { eval_every: 1, patience: 2, min_delta: 0.0 };
+        let monitor = Monitor { x: &x_val, y: &y_val, early_stopping: Some(&stopping) };
+        let (mut model, history) = fit_line(20, Some(&monitor));
+
+        let losses: Vec<f64> = history.epochs.iter().map(|record| record.validation_loss.unwrap()).collect();
+        assert_eq!(history.epochs.len(), 3);
+        assert!(losses[0] < losses[1] && losses[1] < losses[2], "{losses:?}");
+
+        let (best, _) = fit_line(1, None);
+        assert_eq!((model.weights(), model.bias()), (best.weights(), best.bias()));
+        assert!((model.loss_on(&x_val, &y_val) - losses[0]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn monitored_training_leaves_one_batch_in_the_graph() {
+        let (x_val, y_val) = line(-1.0);
+        let stopping = EarlyStoppingConfig { eval_every: 1, patience: 2, min_delta: 0.0 };
+        let monitor = Monitor { x: &x_val, y: &y_val, early_stopping: Some(&stopping) };
+        let (model, _) = fit_line(20, Some(&monitor));
+        let (best, _) = fit_line(1, None);
+
+        // Saved nodes hold a batch of rows, neither the validation rows nor the full training set
+        assert_eq!((model.input().nrows(), model.output().nrows()), (8, 8));
+        assert_eq!((model.weights(), model.bias()), (best.weights(), best.bias()));
+    }
+
+    #[test]
+    fn early_stopping_waits_for_patience_between_checks() {
+        let (x_val, y_val) = line(1.0);
+        let stopping = EarlyStoppingConfig { eval_every: 5, patience: 1, min_delta: 0.0 };
+        let monitor = Monitor { x: &x_val, y: &y_val, early_stopping: Some(&stopping) };
+
+        // Improving validation loss trains every epoch and keeps the last check's parameters
+        let (model, history) = fit_line(20, Some(&monitor));
+        assert_eq!(history.epochs.len(), 20);
+        let (full, _) = fit_line(20, None);
+        assert_eq!((model.weights(), model.bias()), (full.weights(), full.bias()));
+
+        // A min_delta no epoch improves by stops at the first check after the first
+        let stopping = EarlyStoppingConfig { min_delta: 1e6, ..stopping };
+        let monitor = Monitor { early_stopping: Some(&stopping), ..monitor };
+        let (model, history) = fit_line(20, Some(&monitor));
+        assert_eq!(history.epochs.len(), 10);
+        let (first_check, _) = fit_line(5, None);
+        assert_eq!((model.weights(), model.bias()), (first_check.weights(), first_check.bias()));
+    }
+
+    #[test]
+    fn monitor_without_early_stopping_records_every_epoch() {
+        let (x_val, y_val) = line(-1.0);
+        let monitor = Monitor { x: &x_val, y: &y_val, early_stopping: None };
+        let (model, history) = fit_line(12, Some(&monitor));
+
+        assert_eq!(history.epochs.len(), 12);
+        assert!(history.epochs.iter().all(|record| record.validation_loss.is_some()));
+        let (full, _) = fit_line(12, None);
+        assert_eq!(model.weights(), full.weights());
+    }
+
+    /// Pipeline over two synthetic features in a fresh temporary store
+    fn synthetic<M: TabularModel>(name: &str, model: &str, transforms: &str, target: impl Fn(f64, f64) -> f64) -> TabularPipeline<M> {
+        let a: Vec<f64> = (0..48).map(|i| i as f64 * 0.5).collect();
+        let b: Vec<f64> = (0..48).map(|i| (i as f64).sin() * 4.0 + 10.0).collect();
+        let y: Vec<f64> = a.iter().zip(&b).map(|(&a, &b)| target(a, b)).collect();
+
+        let dir = std::env::temp_dir().join("crate_tabular_tests");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{name}.parquet"));
+        let mut df = df!("a" => a, "b" => b, "y" => y).unwrap();
+        ParquetWriter::new(&mut std::fs::File::create(&path).unwrap()).finish(&mut df).unwrap();
+        let output = dir.join(name);
+        let _ = std::fs::remove_dir_all(&output);
+
+        let source = format!(r#"
+            name = "{name}"
+            output_dir = "{}"
+
+            [dataset]
+            path = "{}"
+            features = ["a", "b"]
+            target = "y"
+            test_size = 0.25
+
+            [transforms]
+            {transforms}
+
+            [model]
+            {model}
+            learning_rate = 0.05
+
+            [hyperparameters]
+            iterations = 1
+            batch_size = 8
+            batch_epochs = 30
+        "#, output.display(), path.display());
+        TabularPipeline::<M>::from_config(ExperimentConfig::parse(&source).unwrap()).unwrap()
+    }
+
+    /// Train a model on two synthetic features and return the pipeline
+    fn train<M: TabularModel>(name: &str, model: &str, transforms: &str, target: impl Fn(f64, f64) -> f64) -> TabularPipeline<M> {
+        let mut pipeline = synthetic::<M>(name, model, transforms, target);
+        pipeline.try_load().unwrap();
+        pipeline.try_transform().unwrap();
+        pipeline.try_train().unwrap();
+        pipeline
+    }
+
+    #[test]
+    fn training_saves_history_up_to_the_early_stop() {
+        use crate::history::HISTORY_CSV_FILE;
+
+        let validation_losses = |pipeline: &TabularPipeline<SGD>| {
+            let dir = pipeline.store().dir(VersionSpec::Latest).unwrap();
+            let df = read_table(&format!("{dir}/{HISTORY_CSV_FILE}")).unwrap();
+            let epochs = df.column("epoch").unwrap().cast(&DataType::UInt64).unwrap().u64().unwrap().into_no_null_iter().collect::<Vec<_>>();
+            let losses = df.column("validation_loss").unwrap().cast(&DataType::Float64).unwrap().f64().unwrap().into_iter().collect::<Vec<_>>();
+            (epochs, losses)
+        };
+
+        // Without validation rows every epoch is recorded with a blank validation loss
+        let full = train::<SGD>("history_full", r#"kind = "sgd""#, r#"features = "standard""#, |a, b| a + b);
+        let (epochs, losses) = validation_losses(&full);
+        assert_eq!(epochs, (1..=30).collect::<Vec<u64>>());
+        assert!(losses.iter().all(Option::is_none));
+
+        // A min_delta no epoch improves by stops at the second check
+        let mut pipeline = synthetic::<SGD>("history_early_stop", r#"kind = "sgd""#, r#"features = "standard""#, |a, b| a + b);
+        pipeline.config.dataset.validation_size = 0.2;
+        pipeline.config.early_stopping = Some(EarlyStoppingConfig { eval_every: 1, patience: 1, min_delta: 1e6 });
+        pipeline.try_load().unwrap();
+        pipeline.try_transform().unwrap();
+        pipeline.try_train().unwrap();
+        let (epochs, losses) = validation_losses(&pipeline);
+        assert_eq!(epochs, [1, 2]);
+        assert!(losses.iter().all(Option::is_some), "{losses:?}");
+    }
+
+    #[test]
+    fn evaluate_saves_metrics_and_baselines() {
+        use crate::evaluation::EVALUATION_SUMMARY_FILE;
+
+        let mut unloaded = synthetic::<SGD>("evaluate_unloaded", r#"kind = "sgd""#, r#"features = "standard""#, |a, b| a + b);
+        assert_eq!(unloaded.try_evaluate().unwrap_err().kind(), "stage_order");
+
+        let mut pipeline = train::<SGD>("evaluate", r#"kind = "sgd""#, r#"features = "standard""#, |a, b| 2.0 * a + b);
+        pipeline.try_evaluate().unwrap();
+
+        let dir = pipeline.store().dir(VersionSpec::Latest).unwrap();
+        let evaluation = Evaluation::load(&dir).unwrap();
+        assert_eq!((evaluation.experiment.as_str(), evaluation.partition.as_str()), ("evaluate", "test"));
+        assert_eq!((evaluation.rows, evaluation.seed), (pipeline.indices.test.len(), pipeline.config.seed));
+
+        // Metrics match scoring the saved model on the raw test rows
+        let x_test = pipeline.x.select(Axis(0), &pipeline.indices.test);
+        let y_test = pipeline.y.select(Axis(0), &pipeline.indices.test);
+        let mut saved = SavedModel::<SGD>::load(&dir).unwrap();
+        let scored = saved.score(Task::Regression, &x_test, &y_test).unwrap();
+        for ((name, found), (_, expected)) in evaluation.metrics.scalars().into_iter().zip(scored.scalars()) {
+            assert!((found - expected).abs() < 1e-12, "{name}: {found} != {expected}");
+        }
+        let baselines: Vec<&str> = evaluation.baselines.iter().map(|score| score.baseline.name()).collect();
+        assert!(baselines.contains(&"mean") && baselines.contains(&"median"), "{baselines:?}");
+
+        let md = std::fs::read_to_string(format!("{dir}/{EVALUATION_SUMMARY_FILE}")).unwrap();
+        assert_eq!(md, evaluation.to_markdown());
+        let rmse = evaluation.metrics.get("rmse").unwrap();
+        assert!(md.contains(&format!("| RMSE | {rmse:.4} |")), "{md}");
+        assert!(md.contains(&format!("| trained model | {} |", evaluation.rows)), "{md}");
+        for score in &evaluation.baselines {
+            assert!(md.contains(&format!("| {} | {} |", score.baseline.name(), score.rows)), "{md}");
+        }
+
+        // Reports are written after sealing and leave the version valid
+        let report = validate(&dir);
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+    }
+
+    #[test]
+    fn prune_leaves_sealed_versions_valid() {
+        let pipeline = train::<SGD>("prune_sealed", r#"kind = "sgd""#, r#"features = "standard""#, |a, b| a + b);
+        let store = pipeline.store();
+        let mut manifest = store.resolve(VersionSpec::Latest).unwrap();
+        let dir = store.version_dir(manifest.version);
+
+        // A graph saved to another path, sealed into the version's manifest
+        let stale = format!("{}/{}_nodes.json", crate::store::GRAPH_DIR, crate::store::graph_prefix("elsewhere"));
+        std::fs::write(format!("{dir}/{stale}"), "[]").unwrap();
+        store.seal(&mut manifest).unwrap();
+
+        assert_eq!(store.prune().unwrap(), [format!("{dir}/{stale}")]);
+        let report = validate(&dir);
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+    }
+
+    #[test]
+    fn dendritic_traits_run_stages() {
+        let iris = <TabularPipeline<Logistic> as ModelPipeline>::register("iris");
+        assert_eq!(iris.config().name, "iris");
+
+        let mut pipeline = synthetic::<SGD>("dendritic_traits", r#"kind = "sgd""#, r#"features = "standard""#, |a, b| a - b);
+        Load::load(&mut pipeline);
+        Transform::transform(&mut pipeline);
+        Train::train(&mut pipeline);
+        Evaluate::evaluate(&mut pipeline);
+        Inference::inference(&mut pipeline);
+        assert_eq!(pipeline.store().resolve(VersionSpec::Latest).unwrap().version, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "no_such_experiment.toml")]
+    fn dendritic_register_panics_on_unknown_names() {
+        <TabularPipeline<SGD> as ModelPipeline>::register("no_such_experiment");
+    }
+
+    #[test]
+    fn cross_validation_folds_cover_non_test_rows() {
+        let pipeline = train::<SGD>("cross_validation", r#"kind = "sgd""#, r#"features = "standard""#, |a, b| a + b);
+        let pool = pipeline.indices.train.len() + pipeline.indices.validation.len();
+        let cv = pipeline.cross_validate(3, 2).unwrap();
+
+        assert_eq!(cv.results.len(), 6);
+        for repeat in 0..2 {
+            let folds: Vec<&FoldResult> = cv.results.iter().filter(|result| result.repeat == repeat).collect();
+            assert_eq!(folds.iter().map(|result| result.fold).collect::<Vec<_>>(), [0, 1, 2]);
+            assert_eq!(folds.iter().map(|result| result.validation_rows).sum::<usize>(), pool);
+            assert!(folds.iter().all(|result| result.train_rows + result.validation_rows == pool));
+        }
+        assert_eq!(cv.summary, CrossValidation::summarize(&cv.results));
+        assert_eq!(cv.metric("rmse").unwrap().folds, 6);
+    }
+
+    /// Saved model, held out raw rows and outputs of its ONNX export evaluated in process
+    fn onnx_outputs<M: TabularModel>(pipeline: &TabularPipeline<M>) -> (SavedModel<M>, Array2<f64>, BTreeMap<String, Array2<f64>>) {
+        let dir = pipeline.store().dir(VersionSpec::Latest).unwrap();
+        let saved = SavedModel::<M>::load(&dir).unwrap();
+        let x_test = pipeline.x.select(Axis(0), &pipeline.indices.test);
+
+        // Round trip through the protobuf encoding
+        let exported = OnnxModel::from_export(&saved.export(pipeline.config.task())).unwrap();
+        let decoded = OnnxModel::decode(&exported.encode()).unwrap();
+        assert_eq!(decoded, exported);
+
+        let outputs = decoded.run(&x_test).unwrap();
+        (saved, x_test, outputs)
+    }
+
+    fn assert_close(found: &Array2<f64>, expected: &Array2<f64>) {
+        assert_eq!(found.shape(), expected.shape());
+        for (found, expected) in found.iter().zip(expected) {
+            assert!((found - expected).abs() <= 1e-4 * (1.0 + expected.abs()), "{found} != {expected}");
+        }
+    }
+
+    #[test]
+    fn onnx_regression_matches_predict() {
+        let pipeline = train::<SGD>(
+            "onnx_sgd",
+            r#"kind = "sgd""#,
+            r#"features = "standard"
+            target = "min_max""#,
+            |a, b| 2.0 * a - b + 3.0
+        );
+        let (mut saved, x_test, outputs) = onnx_outputs(&pipeline);
+        assert_close(&outputs["prediction"], &saved.predict(&x_test).unwrap());
+    }
+
+    #[test]
+    fn onnx_binary_matches_predict() {
+        let pipeline = train::<Logistic>(
+            "onnx_binary",
+            r#"kind = "logistic""#,
+            r#"features = "min_max""#,
+            |a, _| if a > 12.0 { 1.0 } else { 0.0 }
+        );
+        let (mut saved, x_test, outputs) = onnx_outputs(&pipeline);
+        let probabilities = saved.predict(&x_test).unwrap();
+        assert_close(&outputs["probabilities"], &probabilities);
+        assert_eq!(outputs["label"], probabilities.mapv(|p| if p > 0.5 { 1.0 } else { 0.0 }));
+    }
+
+    #[test]
+    fn onnx_multiclass_matches_predict() {
+        let pipeline = train::<Logistic>(
+            "onnx_multiclass",
+            r#"kind = "logistic"
+            multi_class = true"#,
+            r#"features = "standard"
+            target = "one_hot""#,
+            |a, _| (a / 8.0).floor()
+        );
+        let (mut saved, x_test, outputs) = onnx_outputs(&pipeline);
+        assert_close(&outputs["probabilities"], &saved.probabilities(&x_test).unwrap());
+        assert_eq!(outputs["label"].column(0), saved.predict(&x_test).unwrap().column(0));
+    }
+
+    #[test]
+    fn export_leaves_version_valid() {
+        let pipeline = train::<SGD>(
+            "export_sgd",
+            r#"kind = "sgd""#,
+            r#"features = "standard""#,
+            |a, b| a + b
+        );
+        let dir = pipeline.store().dir(VersionSpec::Latest).unwrap();
+        let output = std::env::temp_dir().join("crate_tabular_tests/export_sgd_export");
+        let output = output.to_string_lossy();
+
+        assert_eq!(export(&pipeline.config, Some(&output)).unwrap(), output);
+        assert!(Path::new(&*output).join(crate::onnx::ONNX_FILE).is_file());
+        assert_eq!(ExportedModel::load(&output).unwrap(), ExportedModel::load(&dir).unwrap());
+
+        // Exporting into the sealed version directory is refused
+        assert!(matches!(export(&pipeline.config, Some(&dir)), Err(PipelineError::Config(_))));
+        let report = validate(&dir);
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+    }
+
+}